@@ -0,0 +1,68 @@
+use uiua::{Array, Complex, Value};
+use wasm_bindgen::prelude::*;
+
+/// A typed argument pushed onto the Uiua stack before calling a binding
+///
+/// Numbers are passed as `f64` bit patterns, so `NaN`, `Infinity` and values
+/// like `1e-5` arrive exactly rather than going through Uiua number syntax.
+#[wasm_bindgen]
+#[derive(Debug, Clone)]
+pub struct Arg(ArgValue);
+
+#[derive(Debug, Clone)]
+enum ArgValue {
+    Num(f64),
+    Array { data: Vec<f64>, shape: Vec<usize> },
+    Complex(f64, f64),
+    Str(String),
+}
+
+#[wasm_bindgen]
+impl Arg {
+    /// A scalar number
+    pub fn num(n: f64) -> Arg {
+        Arg(ArgValue::Num(n))
+    }
+
+    /// A rank-1 list of numbers
+    pub fn list(data: Vec<f64>) -> Arg {
+        let shape = vec![data.len()];
+        Arg(ArgValue::Array { data, shape })
+    }
+
+    /// A row-major array of numbers with an explicit shape, e.g. `[rows, cols]`
+    pub fn shaped(data: Vec<f64>, shape: Vec<usize>) -> Arg {
+        Arg(ArgValue::Array { data, shape })
+    }
+
+    /// A complex scalar `re + im·i`
+    pub fn complex(re: f64, im: f64) -> Arg {
+        Arg(ArgValue::Complex(re, im))
+    }
+
+    /// A string, pushed as a character array
+    pub fn string(s: String) -> Arg {
+        Arg(ArgValue::Str(s))
+    }
+}
+
+impl Arg {
+    /// Convert into a Uiua value, checking that array shapes match their data
+    pub(crate) fn into_value(self) -> Result<Value, String> {
+        Ok(match self.0 {
+            ArgValue::Num(n) => n.into(),
+            ArgValue::Array { data, shape } => {
+                let expected: usize = shape.iter().product();
+                if expected != data.len() {
+                    return Err(format!(
+                        "Shape {shape:?} needs {expected} elements but {} were given",
+                        data.len()
+                    ));
+                }
+                Array::new(shape, data.as_slice()).into()
+            }
+            ArgValue::Complex(re, im) => Complex::new(re, im).into(),
+            ArgValue::Str(s) => s.into(),
+        })
+    }
+}
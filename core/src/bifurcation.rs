@@ -0,0 +1,227 @@
+//! Bifurcation diagrams for any map in the [registry](crate::maps)
+
+use wasm_bindgen::prelude::*;
+
+use crate::{
+    Diagnostic, ErrorKind, OutputBuffer,
+    maps::{self, MapDef, State, escaped},
+};
+
+/// Refuse sweeps with more columns than this rather than hang the tab
+const MAX_COLUMNS: f64 = 1e7;
+
+/// Where each column of a sweep starts iterating
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IcPolicy {
+    /// Every column starts from `(ic_x, ic_y)`
+    Fixed,
+    /// Every column starts from a fresh random x in (0, 1) with y = `ic_y`
+    Random,
+}
+
+/// How to sweep a parameter for [`bifurcation_sweep`]
+#[wasm_bindgen]
+#[derive(Debug, Clone)]
+pub struct SweepOptions {
+    pub param_min: f64,
+    pub param_max: f64,
+    /// Distance between columns
+    pub d_param: f64,
+    /// Iterates discarded before sampling, so only the attractor is plotted
+    pub transient: u32,
+    /// Points kept per column after the transient
+    pub samples: u32,
+    pub ic_policy: IcPolicy,
+    pub ic_x: f64,
+    pub ic_y: f64,
+    /// Seed for [`IcPolicy::Random`], so a sweep can be redrawn identically
+    pub seed: u32,
+    /// Stop a column once its orbit repeats to within this distance, keeping one
+    /// copy of the cycle instead of `samples` points on it
+    pub dedup_tolerance: Option<f64>,
+}
+
+#[wasm_bindgen]
+impl SweepOptions {
+    #[wasm_bindgen(constructor)]
+    pub fn new(param_min: f64, param_max: f64, d_param: f64) -> SweepOptions {
+        SweepOptions {
+            param_min,
+            param_max,
+            d_param,
+            transient: 1000,
+            samples: 256,
+            ic_policy: IcPolicy::Fixed,
+            ic_x: 0.0,
+            ic_y: 0.0,
+            seed: 1,
+            dedup_tolerance: None,
+        }
+    }
+}
+
+impl SweepOptions {
+    fn columns(&self) -> Result<u32, Diagnostic> {
+        let span = (self.param_max - self.param_min) / self.d_param;
+        if self.d_param <= 0.0 || !span.is_finite() || span < 0.0 {
+            return Err(Diagnostic::new(
+                ErrorKind::Argument,
+                "The sweep needs param_min <= param_max and a positive d_param",
+            ));
+        }
+        if span >= MAX_COLUMNS {
+            return Err(Diagnostic::new(
+                ErrorKind::Argument,
+                format!("A sweep of {span:.0} columns is too large"),
+            ));
+        }
+        // Allow for rounding so that a range like 2..4 by 0.001 includes 4
+        Ok((span + 1e-9).floor() as u32 + 1)
+    }
+}
+
+/// Sweep the parameter `sweep_param` of `map_id` and write `[param, x, y]` rows into `out`
+///
+/// `params` gives the other parameters in registry order; the swept entry is
+/// overwritten. Columns whose orbit escapes are left out.
+#[wasm_bindgen(js_name = bifurcationSweep)]
+pub fn bifurcation_sweep(
+    map_id: &str,
+    params: Vec<f64>,
+    sweep_param: &str,
+    options: &SweepOptions,
+    out: &mut OutputBuffer,
+) -> Result<(), Diagnostic> {
+    let mut columns = Columns::new(map_id, params, sweep_param, options)?;
+    let mut cycle = Vec::new();
+    out.write_rows(3, |rows| {
+        while let Some((value, ic)) = columns.next_column() {
+            if column(columns.map, &columns.params, ic, options, &mut cycle) {
+                rows.extend(cycle.iter().flat_map(|&[x, y]| [value, x, y]));
+            }
+        }
+    });
+    Ok(())
+}
+
+/// The parameter values and initial conditions of a sweep, one column at a time
+pub(crate) struct Columns<'a> {
+    pub map: &'static MapDef,
+    /// The map's parameters, with the swept one set for the current column
+    pub params: Vec<f64>,
+    index: usize,
+    options: &'a SweepOptions,
+    rng: XorShift,
+    next: u32,
+    count: u32,
+}
+
+impl<'a> Columns<'a> {
+    pub fn new(
+        map_id: &str,
+        params: Vec<f64>,
+        sweep_param: &str,
+        options: &'a SweepOptions,
+    ) -> Result<Self, Diagnostic> {
+        let map = maps::find(map_id)?;
+        map.check_params(&params)?;
+        let index = (map.params.iter())
+            .position(|p| p.name == sweep_param)
+            .ok_or_else(|| {
+                Diagnostic::new(
+                    ErrorKind::Argument,
+                    format!("{} has no parameter `{sweep_param}`", map.name),
+                )
+            })?;
+        Ok(Columns {
+            map,
+            params,
+            index,
+            options,
+            rng: XorShift::new(options.seed),
+            next: 0,
+            count: options.columns()?,
+        })
+    }
+
+    /// Advance to the next column, returning its parameter value and initial condition
+    pub fn next_column(&mut self) -> Option<(f64, State)> {
+        if self.next == self.count {
+            return None;
+        }
+        let value = self.options.param_min + self.next as f64 * self.options.d_param;
+        self.next += 1;
+        self.params[self.index] = value;
+        let ic = match self.options.ic_policy {
+            IcPolicy::Fixed => [self.options.ic_x, self.options.ic_y],
+            IcPolicy::Random => [self.rng.next_open01(), self.options.ic_y],
+        };
+        Some((value, ic))
+    }
+}
+
+/// Fill `cycle` with the sampled states of one column, returning `false` if the orbit escapes
+fn column(
+    map: &MapDef,
+    params: &[f64],
+    ic: State,
+    options: &SweepOptions,
+    cycle: &mut Vec<State>,
+) -> bool {
+    cycle.clear();
+    let mut state = ic;
+    // Always run the whole transient: near a bifurcation the orbit can return
+    // close to an earlier state long before it settles onto the attractor
+    for _ in 0..options.transient {
+        state = (map.step)(state, params);
+        if escaped(state) {
+            return false;
+        }
+    }
+    for _ in 0..options.samples {
+        cycle.push(state);
+        state = (map.step)(state, params);
+        if escaped(state) {
+            return false;
+        }
+        if let Some(tol) = options.dedup_tolerance
+            && close(state, cycle[0], tol)
+            && repeats(map, params, state, cycle, tol)
+        {
+            return true;
+        }
+    }
+    true
+}
+
+/// Whether the orbit from `state` retraces `cycle` for one full period
+fn repeats(map: &MapDef, params: &[f64], mut state: State, cycle: &[State], tol: f64) -> bool {
+    cycle.iter().all(|&expected| {
+        let matched = close(state, expected, tol);
+        state = (map.step)(state, params);
+        matched
+    })
+}
+
+fn close(a: State, b: State, tol: f64) -> bool {
+    (a[0] - b[0]).abs() <= tol && (a[1] - b[1]).abs() <= tol
+}
+
+/// A small deterministic generator for random initial conditions
+struct XorShift(u64);
+
+impl XorShift {
+    fn new(seed: u32) -> Self {
+        // xorshift must not start at zero
+        XorShift(u64::from(seed) ^ 0x9E37_79B9_7F4A_7C15)
+    }
+
+    /// A uniform sample from the open interval (0, 1)
+    fn next_open01(&mut self) -> f64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        ((self.0 >> 11) as f64 + 0.5) / (1u64 << 53) as f64
+    }
+}
@@ -0,0 +1,299 @@
+//! Evaluate a Uiua module natively through the same engine path as the browser
+//!
+//! ```text
+//! chaos-eval [--format table|json|csv] [--time-limit MS] <module.ua> [expression...]
+//! ```
+//!
+//! Imports resolve against the `uiua-modules/` embedded in this binary, so
+//! results match what `run_algo` returns in wasm.
+
+use std::{fmt::Write, process::ExitCode};
+
+use chaos_engine::{Diagnostic, Limits, Output, ValueKind};
+
+const USAGE: &str = "\
+Usage: chaos-eval [--format table|json|csv] [--time-limit MS] <module.ua> [expression...]
+Example: chaos-eval uiua-modules/cobweb.ua 'CobwebPath 51 3.7 0.2'";
+
+#[derive(Clone, Copy)]
+enum Format {
+    Table,
+    Json,
+    Csv,
+}
+
+struct Options {
+    format: Format,
+    time_limit_ms: Option<f64>,
+    module: String,
+    expr: String,
+}
+
+fn main() -> ExitCode {
+    let opts = match parse_args(std::env::args().skip(1)) {
+        Ok(opts) => opts,
+        // `--help` is reported as an empty error
+        Err(e) if e.is_empty() => {
+            println!("{USAGE}");
+            return ExitCode::SUCCESS;
+        }
+        Err(e) => {
+            eprintln!("{e}\n{USAGE}");
+            return ExitCode::from(2);
+        }
+    };
+    let module = match std::fs::read_to_string(&opts.module) {
+        Ok(module) => module,
+        Err(e) => {
+            eprintln!("Cannot read {}: {e}", opts.module);
+            return ExitCode::from(2);
+        }
+    };
+    let code = format!("{module}\n{}", opts.expr);
+    let mut limits = Limits::new();
+    limits.time_limit_ms = opts.time_limit_ms;
+    match chaos_engine::run(&code, limits) {
+        Ok(values) => {
+            let out = match opts.format {
+                Format::Table => table(&values),
+                Format::Json => json(&values),
+                Format::Csv => csv(&values),
+            };
+            print!("{out}");
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("{}", describe(&e, &opts.module, &module));
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Options, String> {
+    let mut format = Format::Table;
+    let mut time_limit_ms = None;
+    let mut positional = Vec::new();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-f" | "--format" => {
+                format = match args.next().as_deref() {
+                    Some("table") => Format::Table,
+                    Some("json") => Format::Json,
+                    Some("csv") => Format::Csv,
+                    other => return Err(format!("Unknown format {other:?}")),
+                }
+            }
+            "-t" | "--time-limit" => {
+                let ms = args.next().and_then(|ms| ms.parse().ok());
+                time_limit_ms = Some(ms.ok_or("--time-limit needs a number of milliseconds")?);
+            }
+            "-h" | "--help" => return Err(String::new()),
+            _ => positional.push(arg),
+        }
+    }
+    if positional.is_empty() {
+        return Err("Missing module path".into());
+    }
+    let module = positional.remove(0);
+    Ok(Options {
+        format,
+        time_limit_ms,
+        module,
+        expr: positional.join(" "),
+    })
+}
+
+/// Format a diagnostic as `file:line:col: Kind: message`
+///
+/// Spans without a file are in the module at `path`, whose source is
+/// `module`, or in the expression, reported as `<expr>`, when they fall past
+/// the module's last line.
+fn describe(e: &Diagnostic, path: &str, module: &str) -> String {
+    // The expression follows the module after a newline
+    let module_lines = module.matches('\n').count() + 1;
+    let location = match &e.span {
+        Some(span) => match span.file.as_deref() {
+            Some(file) => format!("{file}:{}:{}: ", span.line, span.column),
+            None if span.line as usize > module_lines => {
+                format!(
+                    "<expr>:{}:{}: ",
+                    span.line as usize - module_lines,
+                    span.column
+                )
+            }
+            None => format!("{path}:{}:{}: ", span.line, span.column),
+        },
+        None => String::new(),
+    };
+    format!("{location}{:?}: {}", e.kind, e.message)
+}
+
+/// The `i`th element of `out` as text; complex numbers are written `re+imi`
+fn element(out: &Output, i: usize) -> String {
+    match out.kind {
+        ValueKind::Complex => {
+            let (re, im) = (out.data[2 * i], out.data[2 * i + 1]);
+            format!("{re}{}{}i", if im < 0.0 { "" } else { "+" }, im)
+        }
+        _ => out.data[i].to_string(),
+    }
+}
+
+/// Elements of `out` split into rows along its last axis
+fn rows(out: &Output) -> Vec<Vec<String>> {
+    let len = out.shape.iter().product::<usize>();
+    let width = out.shape.last().copied().unwrap_or(1).max(1);
+    let cells: Vec<String> = (0..len).map(|i| element(out, i)).collect();
+    cells.chunks(width).map(<[String]>::to_vec).collect()
+}
+
+fn header(out: &Output) -> String {
+    let shape: Vec<String> = out.shape.iter().map(usize::to_string).collect();
+    let mut header = format!("# {:?} [{}]", out.kind, shape.join(" "));
+    if let Some(label) = &out.label {
+        write!(header, " ${label}").unwrap();
+    }
+    header
+}
+
+/// Each value with a header line giving its kind, shape and label, then its rows
+fn table(values: &[Output]) -> String {
+    let mut s = String::new();
+    for (i, out) in values.iter().enumerate() {
+        if i > 0 {
+            s.push('\n');
+        }
+        table_value(out, 0, &mut s);
+    }
+    s
+}
+
+fn table_value(out: &Output, depth: usize, s: &mut String) {
+    let indent = "  ".repeat(depth);
+    writeln!(s, "{indent}{}", header(out)).unwrap();
+    match out.kind {
+        ValueKind::Char => writeln!(s, "{indent}{}", out.text.as_deref().unwrap_or("")).unwrap(),
+        ValueKind::Box => out
+            .items
+            .iter()
+            .for_each(|item| table_value(item, depth + 1, s)),
+        _ => {
+            let rows = rows(out);
+            let width = rows.iter().flatten().map(String::len).max().unwrap_or(0);
+            for row in rows {
+                let cells: Vec<String> = row.iter().map(|c| format!("{c:>width$}")).collect();
+                writeln!(s, "{indent}{}", cells.join(" ")).unwrap();
+            }
+        }
+    }
+}
+
+/// One JSON array of values, top of the stack first
+///
+/// Non-finite numbers have no JSON form and are written as `null`.
+fn json(values: &[Output]) -> String {
+    let items: Vec<String> = values.iter().map(json_value).collect();
+    format!("[{}]\n", items.join(","))
+}
+
+fn json_value(out: &Output) -> String {
+    let shape: Vec<String> = out.shape.iter().map(usize::to_string).collect();
+    let mut s = format!(
+        "{{\"kind\":\"{:?}\",\"shape\":[{}]",
+        out.kind,
+        shape.join(",")
+    );
+    if let Some(label) = &out.label {
+        write!(s, ",\"label\":{}", json_string(label)).unwrap();
+    }
+    match out.kind {
+        ValueKind::Char => write!(
+            s,
+            ",\"text\":{}",
+            json_string(out.text.as_deref().unwrap_or(""))
+        )
+        .unwrap(),
+        ValueKind::Box => {
+            let items: Vec<String> = out.items.iter().map(json_value).collect();
+            write!(s, ",\"items\":[{}]", items.join(",")).unwrap();
+        }
+        _ => {
+            let data: Vec<String> = (out.data.iter())
+                .map(|x| match x.is_finite() {
+                    true => x.to_string(),
+                    false => "null".into(),
+                })
+                .collect();
+            write!(s, ",\"data\":[{}]", data.join(",")).unwrap();
+        }
+    }
+    s.push('}');
+    s
+}
+
+fn json_string(text: &str) -> String {
+    let mut s = String::from('"');
+    for c in text.chars() {
+        match c {
+            '"' => s.push_str("\\\""),
+            '\\' => s.push_str("\\\\"),
+            '\n' => s.push_str("\\n"),
+            c if (c as u32) < 0x20 => write!(s, "\\u{:04x}", c as u32).unwrap(),
+            c => s.push(c),
+        }
+    }
+    s.push('"');
+    s
+}
+
+/// Rows along each value's last axis; values are separated by a blank line
+fn csv(values: &[Output]) -> String {
+    let mut s = String::new();
+    for (i, out) in values.iter().enumerate() {
+        if i > 0 {
+            s.push('\n');
+        }
+        match out.kind {
+            ValueKind::Char => writeln!(s, "{}", out.text.as_deref().unwrap_or("")).unwrap(),
+            ValueKind::Box => s.push_str(&csv(&out.items)),
+            _ => rows(out)
+                .iter()
+                .for_each(|row| writeln!(s, "{}", row.join(",")).unwrap()),
+        }
+    }
+    s
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The message `chaos-eval` prints when `expr` fails after `module`
+    fn error(module: &str, expr: &str) -> String {
+        let code = format!("{module}\n{expr}");
+        let e = chaos_engine::run(&code, Limits::new()).unwrap_err();
+        describe(&e, "maps.ua", module)
+    }
+
+    #[test]
+    fn errors_are_attributed_to_their_source() {
+        let module = "~ \"prelude.ua\" ~ Domain\nX ← 1\n";
+        assert_eq!(
+            error("X ← 1\nY ← foo", "Y"),
+            "maps.ua:2:5: Compile: Unknown identifier `foo`"
+        );
+        assert_eq!(
+            error(module, "foo 1"),
+            "<expr>:1:1: Compile: Unknown identifier `foo`"
+        );
+        assert_eq!(
+            error(module, "1\nfoo 1"),
+            "<expr>:2:1: Compile: Unknown identifier `foo`"
+        );
+        assert_eq!(
+            error(module, "Domain 2 [1 2] [1 2 3]"),
+            "prelude.ua:10:16: Runtime: Shapes [2] and [3] are not compatible"
+        );
+        assert_eq!(error("X ← 1", ""), "Pop: No result was left on the stack");
+    }
+}
@@ -0,0 +1,160 @@
+use uiua::Value;
+use wasm_bindgen::prelude::*;
+
+use crate::{Diagnostic, ErrorKind, ValueKind};
+
+/// A reusable result buffer that JavaScript reads in place
+///
+/// [`UiuaSession::call_into`](crate::UiuaSession::call_into) overwrites the
+/// buffer's contents while keeping its allocation, so a plot that is redrawn
+/// every frame neither allocates on the wasm side nor copies into a new JS array.
+///
+/// Views returned by [`view`](OutputBuffer::view) and [`view_f32`](OutputBuffer::view_f32)
+/// point straight into wasm memory. They are only valid until the next call
+/// into the engine, which may grow memory and detach them, so read or upload
+/// them immediately and ask for a new view each frame.
+#[wasm_bindgen]
+#[derive(Debug, Clone)]
+pub struct OutputBuffer {
+    kind: ValueKind,
+    shape: Vec<usize>,
+    data: Vec<f64>,
+    /// Single-precision copy for [`view_f32`](OutputBuffer::view_f32), kept to reuse its memory
+    narrow: Vec<f32>,
+}
+
+impl Default for OutputBuffer {
+    fn default() -> Self {
+        OutputBuffer {
+            kind: ValueKind::Num,
+            shape: Vec::new(),
+            data: Vec::new(),
+            narrow: Vec::new(),
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl OutputBuffer {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> OutputBuffer {
+        OutputBuffer::default()
+    }
+
+    /// Element type of the last value written; complex data is interleaved re/im
+    #[wasm_bindgen(getter)]
+    pub fn kind(&self) -> ValueKind {
+        self.kind
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn shape(&self) -> Vec<usize> {
+        self.shape.clone()
+    }
+
+    /// Number of `f64`s in the buffer
+    #[wasm_bindgen(getter)]
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// A `Float64Array` over the buffer, without copying
+    pub fn view(&self) -> js_sys::Float64Array {
+        // SAFETY: the view is documented as invalid after the next engine call,
+        // and no engine code runs while JS holds it
+        unsafe { js_sys::Float64Array::view(&self.data) }
+    }
+
+    /// A `Float32Array` over a single-precision copy, e.g. for WebGL vertex buffers
+    ///
+    /// The copy is made into memory reused across calls.
+    pub fn view_f32(&mut self) -> js_sys::Float32Array {
+        self.narrow.clear();
+        self.narrow.extend(self.data.iter().map(|&x| x as f32));
+        // SAFETY: as for `view`
+        unsafe { js_sys::Float32Array::view(&self.narrow) }
+    }
+}
+
+impl OutputBuffer {
+    /// The buffer's contents, for native callers
+    pub fn data(&self) -> &[f64] {
+        &self.data
+    }
+
+    /// Replace the contents, keeping the existing allocation
+    pub(crate) fn write(
+        &mut self,
+        kind: ValueKind,
+        shape: &[usize],
+        data: impl IntoIterator<Item = f64>,
+    ) {
+        self.kind = kind;
+        self.shape.clear();
+        self.shape.extend_from_slice(shape);
+        self.data.clear();
+        self.data.extend(data);
+    }
+
+    /// Replace the contents with rows of `width` numbers pushed by `fill`
+    ///
+    /// A width of 0 leaves the buffer empty with shape `[0 0]`.
+    pub(crate) fn write_rows(&mut self, width: usize, fill: impl FnOnce(&mut Vec<f64>)) {
+        self.kind = ValueKind::Num;
+        self.data.clear();
+        fill(&mut self.data);
+        if width == 0 {
+            self.data.clear();
+        }
+        let rows = self.data.len().checked_div(width).unwrap_or(0);
+        self.shape.clear();
+        self.shape.extend([rows, width]);
+    }
+
+    /// Copy a numeric Uiua array into the buffer
+    pub(crate) fn fill(&mut self, val: &Value) -> Result<(), Diagnostic> {
+        let shape: Vec<usize> = val.shape.iter().copied().collect();
+        match val {
+            Value::Num(arr) => self.write(ValueKind::Num, &shape, arr.elements().copied()),
+            Value::Byte(arr) => {
+                self.write(ValueKind::Byte, &shape, arr.elements().map(|&b| b as f64))
+            }
+            Value::Complex(arr) => self.write(
+                ValueKind::Complex,
+                &shape,
+                arr.elements().flat_map(|c| [c.re, c.im]),
+            ),
+            Value::Char(_) | Value::Box(_) => {
+                return Err(Diagnostic::new(
+                    ErrorKind::UnsupportedValue,
+                    format!(
+                        "Only numeric arrays can be written to a buffer, but the result is {}",
+                        val.type_name_plural()
+                    ),
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rows_set_the_shape() {
+        let mut buf = OutputBuffer::new();
+        buf.write_rows(3, |data| data.extend([1.0, 2.0, 3.0, 4.0, 5.0, 6.0]));
+        assert_eq!(buf.shape(), [2, 3]);
+        assert_eq!(buf.data(), [1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+
+        buf.write_rows(0, |data| data.push(1.0));
+        assert_eq!(buf.shape(), [0, 0]);
+        assert!(buf.is_empty());
+    }
+}
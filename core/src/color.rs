@@ -0,0 +1,350 @@
+//! Colormaps that turn grids of numbers into RGBA pixels for `ImageData`
+//!
+//! A [`Colormap`] is a gradient through color stops, either one of the
+//! built-in perceptual maps or stops given by the caller. [`ColorOptions`]
+//! says how values are scaled onto it, and [`colorize`] writes the pixels into
+//! an [`RgbaImage`], which keeps its allocations across frames the way
+//! [`OutputBuffer`](crate::OutputBuffer) does.
+
+use wasm_bindgen::prelude::*;
+
+use crate::{Diagnostic, ErrorKind, fractal::EscapeGrid};
+
+/// Colors precomputed per colormap; finer than any gradient needs
+const LUT_SIZE: usize = 1024;
+
+/// matplotlib's viridis at 11 evenly spaced points
+const VIRIDIS: [u32; 11] = [
+    0x440154, 0x482475, 0x414487, 0x355f8d, 0x2a788e, 0x21918c, 0x22a884, 0x44bf70, 0x7ad151,
+    0xbddf26, 0xfde725,
+];
+
+/// matplotlib's magma at 11 evenly spaced points
+const MAGMA: [u32; 11] = [
+    0x000004, 0x140e36, 0x3b0f70, 0x641a80, 0x8c2981, 0xb73779, 0xde4968, 0xf7705c, 0xfe9f6d,
+    0xfecf92, 0xfcfdbf,
+];
+
+/// Close to matplotlib's twilight, which starts and ends on the same color so
+/// it cycles without a seam
+const TWILIGHT: [u32; 9] = [
+    0xe2d9e2, 0xa7b9d2, 0x6079bb, 0x5f4094, 0x2f1436, 0x842c4f, 0xb45c4a, 0xcc9f8f, 0xe2d9e2,
+];
+
+const GRAYSCALE: [u32; 2] = [0x000000, 0xffffff];
+
+/// Built-in colormaps by name, each with evenly spaced stops
+const NAMED: [(&str, &[u32]); 4] = [
+    ("viridis", &VIRIDIS),
+    ("magma", &MAGMA),
+    ("twilight", &TWILIGHT),
+    ("grayscale", &GRAYSCALE),
+];
+
+/// A gradient from `t = 0` to `t = 1`, interpolated linearly between stops
+#[wasm_bindgen]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Colormap {
+    lut: Vec<[u8; 3]>,
+}
+
+#[wasm_bindgen]
+impl Colormap {
+    /// A gradient through `colors` (`#rrggbb`) placed at ascending `positions` in [0, 1]
+    ///
+    /// Values before the first stop or after the last take its color.
+    #[wasm_bindgen(constructor)]
+    pub fn new(positions: Vec<f64>, colors: Vec<String>) -> Result<Colormap, Diagnostic> {
+        if positions.len() != colors.len() || positions.len() < 2 {
+            return Err(Diagnostic::new(
+                ErrorKind::Argument,
+                "A colormap needs at least two stops, with one position per color",
+            ));
+        }
+        let ascending = positions.windows(2).all(|w| w[0] <= w[1]);
+        let in_range = positions.iter().all(|t| (0.0..=1.0).contains(t));
+        if !ascending || !in_range {
+            return Err(Diagnostic::new(
+                ErrorKind::Argument,
+                "Colormap positions must ascend from 0 to 1",
+            ));
+        }
+        let stops = (positions.into_iter().zip(&colors))
+            .map(|(t, color)| Ok((t, parse_hex(color)?)))
+            .collect::<Result<Vec<_>, Diagnostic>>()?;
+        Ok(Colormap::from_stops(&stops))
+    }
+
+    /// One of the built-in colormaps listed by [`names`](Colormap::names)
+    pub fn named(name: &str) -> Result<Colormap, Diagnostic> {
+        let (_, colors) = (NAMED.iter().find(|(n, _)| *n == name)).ok_or_else(|| {
+            Diagnostic::new(ErrorKind::Argument, format!("Unknown colormap `{name}`"))
+        })?;
+        let last = (colors.len() - 1) as f64;
+        let stops: Vec<(f64, [u8; 3])> = (colors.iter().enumerate())
+            .map(|(i, &hex)| {
+                (
+                    i as f64 / last,
+                    [(hex >> 16) as u8, (hex >> 8) as u8, hex as u8],
+                )
+            })
+            .collect();
+        Ok(Colormap::from_stops(&stops))
+    }
+
+    /// Names of the built-in colormaps
+    pub fn names() -> Vec<String> {
+        NAMED.iter().map(|(name, _)| name.to_string()).collect()
+    }
+
+    /// The color at `t` in [0, 1] as `#rrggbb`, e.g. for a legend
+    #[wasm_bindgen(js_name = colorAt)]
+    pub fn color_at(&self, t: f64) -> String {
+        let [r, g, b] = self.lookup(t);
+        format!("#{r:02x}{g:02x}{b:02x}")
+    }
+}
+
+impl Colormap {
+    fn from_stops(stops: &[(f64, [u8; 3])]) -> Colormap {
+        let lut = (0..LUT_SIZE)
+            .map(|i| {
+                let t = i as f64 / (LUT_SIZE - 1) as f64;
+                let next = stops.partition_point(|&(p, _)| p < t);
+                if next == 0 {
+                    return stops[0].1;
+                }
+                if next == stops.len() {
+                    return stops[next - 1].1;
+                }
+                let ((t0, c0), (t1, c1)) = (stops[next - 1], stops[next]);
+                let f = if t1 > t0 { (t - t0) / (t1 - t0) } else { 1.0 };
+                std::array::from_fn(|k| {
+                    (c0[k] as f64 + f * (c1[k] as f64 - c0[k] as f64)).round() as u8
+                })
+            })
+            .collect();
+        Colormap { lut }
+    }
+
+    /// The color at `t`, clamped to [0, 1]
+    pub fn lookup(&self, t: f64) -> [u8; 3] {
+        let i = (t.clamp(0.0, 1.0) * (LUT_SIZE - 1) as f64).round() as usize;
+        self.lut[i]
+    }
+}
+
+fn parse_hex(color: &str) -> Result<[u8; 3], Diagnostic> {
+    let digits = (color.strip_prefix('#')).filter(|d| d.len() == 6 && d.is_ascii());
+    let rgb = digits.and_then(|d| {
+        let channel = |k: usize| u8::from_str_radix(&d[2 * k..2 * k + 2], 16).ok();
+        Some([channel(0)?, channel(1)?, channel(2)?])
+    });
+    rgb.ok_or_else(|| {
+        Diagnostic::new(
+            ErrorKind::Argument,
+            format!("Color `{color}` is not of the form #rrggbb"),
+        )
+    })
+}
+
+/// How values are spread over a colormap
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scale {
+    /// Evenly from `min` to `max`
+    Linear,
+    /// By ln(1 + value − min), giving more of the colormap to small values
+    Log,
+    /// By rank, so each color covers about as many pixels as any other
+    Histogram,
+}
+
+/// How [`colorize`] maps values to colors
+#[wasm_bindgen(getter_with_clone)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColorOptions {
+    pub scale: Scale,
+    /// Value at the start of the colormap, or the smallest value if unset
+    pub min: Option<f64>,
+    /// Value at the end of the colormap, or the largest value if unset
+    pub max: Option<f64>,
+    /// Repeat the colormap every `period` of the scaled range, instead of
+    /// clamping to it; suits cyclic maps like twilight
+    pub period: Option<f64>,
+    /// Shift of the colormap when it repeats, as a fraction of one period
+    pub offset: f64,
+    /// Color (`#rrggbb`) for values that are not finite and points inside a fractal
+    pub fill: String,
+}
+
+#[wasm_bindgen]
+impl ColorOptions {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> ColorOptions {
+        ColorOptions::default()
+    }
+}
+
+impl Default for ColorOptions {
+    fn default() -> Self {
+        ColorOptions {
+            scale: Scale::Linear,
+            min: None,
+            max: None,
+            period: None,
+            offset: 0.0,
+            fill: "#000000".into(),
+        }
+    }
+}
+
+/// RGBA8 pixels, row-major from the top-left, ready for `ImageData`
+///
+/// As with [`OutputBuffer`](crate::OutputBuffer), the view returned to JS is
+/// only valid until the next call into the engine, so copy it into the
+/// `ImageData` straight away.
+#[wasm_bindgen]
+#[derive(Debug, Clone, Default)]
+pub struct RgbaImage {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+    /// The values being colored, kept to reuse their memory
+    values: Vec<f64>,
+    /// Sorted finite values for histogram equalisation
+    sorted: Vec<f64>,
+}
+
+#[wasm_bindgen]
+impl RgbaImage {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> RgbaImage {
+        RgbaImage::default()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// The pixels as a `Uint8ClampedArray`, without copying
+    #[wasm_bindgen(js_name = data)]
+    pub fn data_view(&self) -> js_sys::Uint8ClampedArray {
+        // SAFETY: as for `OutputBuffer::view`
+        unsafe { js_sys::Uint8ClampedArray::view(&self.pixels) }
+    }
+}
+
+impl RgbaImage {
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    /// Color `self.values` into `self.pixels`
+    fn paint(
+        &mut self,
+        width: u32,
+        height: u32,
+        colormap: &Colormap,
+        options: &ColorOptions,
+    ) -> Result<(), Diagnostic> {
+        let fill = parse_hex(&options.fill)?;
+        if options.period.is_some_and(|p| p.is_nan() || p <= 0.0) {
+            return Err(Diagnostic::new(
+                ErrorKind::Argument,
+                "A colormap period must be positive",
+            ));
+        }
+        let finite = || self.values.iter().copied().filter(|v| v.is_finite());
+        let lo = (options.min).unwrap_or_else(|| finite().fold(f64::INFINITY, f64::min));
+        let hi = (options.max).unwrap_or_else(|| finite().fold(f64::NEG_INFINITY, f64::max));
+        self.sorted.clear();
+        if options.scale == Scale::Histogram {
+            self.sorted.extend(finite());
+            self.sorted.sort_unstable_by(f64::total_cmp);
+        }
+        let scale = |v: f64| -> f64 {
+            let t = match options.scale {
+                Scale::Linear => (v - lo) / (hi - lo),
+                Scale::Log => (v - lo).max(0.0).ln_1p() / (hi - lo).ln_1p(),
+                Scale::Histogram => {
+                    self.sorted.partition_point(|&x| x <= v) as f64 / self.sorted.len() as f64
+                }
+            };
+            // A range of one value puts everything at the start of the colormap
+            if hi == lo { 0.0 } else { t }
+        };
+
+        self.width = width;
+        self.height = height;
+        self.pixels.clear();
+        for &v in &self.values {
+            let t = if v.is_finite() { scale(v) } else { f64::NAN };
+            let t = match options.period {
+                Some(period) => (t / period + options.offset).rem_euclid(1.0),
+                None => t,
+            };
+            let [r, g, b] = if t.is_nan() { fill } else { colormap.lookup(t) };
+            self.pixels.extend([r, g, b, 255]);
+        }
+        Ok(())
+    }
+}
+
+/// Color a `width` × `height` grid of `values` into `out`
+///
+/// Works for any scalar field: Lyapunov exponents, densities, escape counts.
+/// With [`Scale::Histogram`], `min` and `max` are ignored.
+#[wasm_bindgen]
+pub fn colorize(
+    values: &[f64],
+    width: u32,
+    height: u32,
+    colormap: &Colormap,
+    options: &ColorOptions,
+    out: &mut RgbaImage,
+) -> Result<(), Diagnostic> {
+    if values.len() as u64 != u64::from(width) * u64::from(height) {
+        return Err(Diagnostic::new(
+            ErrorKind::Argument,
+            format!(
+                "{} values do not fill a {width}×{height} grid",
+                values.len()
+            ),
+        ));
+    }
+    out.values.clear();
+    out.values.extend_from_slice(values);
+    out.paint(width, height, colormap, options)
+}
+
+/// Color an escape-time render into `out`, painting points in the set with `fill`
+///
+/// Uses the smooth counts if the render produced them, and the integer counts otherwise.
+#[wasm_bindgen(js_name = colorizeEscape)]
+pub fn colorize_escape(
+    grid: &EscapeGrid,
+    colormap: &Colormap,
+    options: &ColorOptions,
+    out: &mut RgbaImage,
+) -> Result<(), Diagnostic> {
+    let smooth = grid.smooth();
+    out.values.clear();
+    out.values
+        .extend((grid.counts().iter().enumerate()).map(|(i, &count)| {
+            if count >= grid.max_iter() {
+                f64::NAN
+            } else if smooth.is_empty() {
+                count as f64
+            } else {
+                smooth[i]
+            }
+        }));
+    out.paint(grid.width(), grid.height(), colormap, options)
+}
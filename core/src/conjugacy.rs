@@ -0,0 +1,105 @@
+//! Numerical checks that a map `C` conjugates `G` to `g`, i.e. C∘G = g∘C
+
+use wasm_bindgen::prelude::*;
+
+use crate::{Diagnostic, ErrorKind, curve::Expression};
+
+/// How well C∘G = g∘C holds at evenly spaced samples of a domain
+#[wasm_bindgen(getter_with_clone)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConjugacyReport {
+    /// The sample points
+    pub xs: Vec<f64>,
+    /// `|C(G(x)) − g(C(x))|` at each sample, NaN where either side is undefined
+    pub residuals: Vec<f64>,
+    /// Largest finite residual
+    pub max_error: f64,
+    /// Where the largest finite residual occurs
+    pub max_at: f64,
+    /// Root mean square of the finite residuals
+    pub rms_error: f64,
+    /// Samples where either side is not a finite number
+    pub undefined: u32,
+    /// Runs of consecutive failing samples as `[start, end, start, end, ...]`
+    ///
+    /// A sample fails if its residual exceeds the tolerance or is undefined.
+    pub failures: Vec<f64>,
+}
+
+#[wasm_bindgen]
+impl ConjugacyReport {
+    /// Whether the relation held at every sample
+    #[wasm_bindgen(getter)]
+    pub fn holds(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+/// Check C∘G = g∘C at `n + 1` evenly spaced points of `[x_min, x_max]`
+///
+/// A sample fails when its residual exceeds `tolerance · (1 + |g(C(x))|)`,
+/// so the tolerance is absolute near 0 and relative for large values.
+#[wasm_bindgen(js_name = checkConjugacy)]
+pub fn check_conjugacy(
+    big_g: &Expression,
+    small_g: &Expression,
+    c: &Expression,
+    x_min: f64,
+    x_max: f64,
+    n: u32,
+    tolerance: f64,
+) -> Result<ConjugacyReport, Diagnostic> {
+    let valid = x_min < x_max && tolerance >= 0.0;
+    if n == 0 || !valid {
+        return Err(Diagnostic::new(
+            ErrorKind::Argument,
+            "The check needs x_min < x_max, at least one interval and a tolerance >= 0",
+        ));
+    }
+    let mut report = ConjugacyReport {
+        xs: Vec::with_capacity(n as usize + 1),
+        residuals: Vec::with_capacity(n as usize + 1),
+        max_error: 0.0,
+        max_at: f64::NAN,
+        rms_error: 0.0,
+        undefined: 0,
+        failures: Vec::new(),
+    };
+    let (mut sum_sq, mut finite) = (0.0, 0u32);
+    // Start of the current run of failures
+    let mut failing_since = None;
+    for i in 0..=n {
+        let x = x_min + (i as f64 / n as f64) * (x_max - x_min);
+        let lhs = c.eval(big_g.eval(x));
+        let rhs = small_g.eval(c.eval(x));
+        let (residual, failed) = if lhs.is_finite() && rhs.is_finite() {
+            let residual = (lhs - rhs).abs();
+            if residual > report.max_error || report.max_at.is_nan() {
+                (report.max_error, report.max_at) = (residual, x);
+            }
+            sum_sq += residual * residual;
+            finite += 1;
+            (residual, residual > tolerance * (1.0 + rhs.abs()))
+        } else {
+            report.undefined += 1;
+            (f64::NAN, true)
+        };
+        report.xs.push(x);
+        report.residuals.push(residual);
+        match (failed, failing_since) {
+            (true, None) => failing_since = Some(x),
+            (false, Some(start)) => {
+                report.failures.extend([start, report.xs[i as usize - 1]]);
+                failing_since = None;
+            }
+            _ => {}
+        }
+    }
+    if let Some(start) = failing_since {
+        report.failures.extend([start, report.xs[n as usize]]);
+    }
+    if finite > 0 {
+        report.rms_error = (sum_sq / finite as f64).sqrt();
+    }
+    Ok(report)
+}
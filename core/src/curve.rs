@@ -0,0 +1,76 @@
+//! Functions of `x` typed in by the user, for the cobweb and conjugacy views
+//!
+//! Replaces compiling the text as JavaScript: an [`Expression`] can only do
+//! arithmetic, reports syntax errors with their column, and samples whole
+//! curves and cobweb paths in one call.
+
+use wasm_bindgen::prelude::*;
+
+use crate::{
+    Diagnostic,
+    expr::{Dual, Expr},
+};
+
+/// A parsed function of `x`, e.g. `4*x*(1-x)` or `Math.sin(x)`
+#[wasm_bindgen]
+#[derive(Debug, Clone)]
+pub struct Expression {
+    expr: Expr,
+}
+
+#[wasm_bindgen]
+impl Expression {
+    /// Parse `source`; errors carry the column of the problem
+    #[wasm_bindgen(constructor)]
+    pub fn new(source: &str) -> Result<Expression, Diagnostic> {
+        Ok(Expression {
+            expr: Expr::parse(source, &["x"], &[])?,
+        })
+    }
+
+    pub fn eval(&self, x: f64) -> f64 {
+        self.expr.eval(&[x], &[])
+    }
+
+    /// The exact derivative f′(x), by automatic differentiation
+    pub fn derivative(&self, x: f64) -> f64 {
+        self.expr.eval(&[Dual::variable(x, 0)], &[]).grad[0]
+    }
+
+    /// The function at every element of `xs`
+    #[wasm_bindgen(js_name = evalMany)]
+    pub fn eval_many(&self, xs: &[f64]) -> Vec<f64> {
+        xs.iter().map(|&x| self.eval(x)).collect()
+    }
+
+    /// `n + 1` points of the curve over `[x_min, x_max]`, interleaved `[x0, y0, x1, y1, ...]`
+    ///
+    /// With `n` = 0 this is the single point at `x_min`.
+    #[wasm_bindgen(js_name = sampleCurve)]
+    pub fn sample_curve(&self, x_min: f64, x_max: f64, n: u32) -> Vec<f64> {
+        (0..=n)
+            .flat_map(|i| {
+                let x = x_min + (i as f64 / n.max(1) as f64) * (x_max - x_min);
+                [x, self.eval(x)]
+            })
+            .collect()
+    }
+
+    /// Cobweb path `(x0, 0) → (x0, f(x0)) → (f(x0), f(x0)) → ...` for `n` steps, interleaved
+    ///
+    /// Stops after the first step whose result is not finite.
+    #[wasm_bindgen(js_name = cobwebPath)]
+    pub fn cobweb_path(&self, x0: f64, n: u32) -> Vec<f64> {
+        let mut path = vec![x0, 0.0];
+        let mut x = x0;
+        for _ in 0..n {
+            let y = self.eval(x);
+            path.extend([x, y, y, y]);
+            x = y;
+            if !x.is_finite() {
+                break;
+            }
+        }
+        path
+    }
+}
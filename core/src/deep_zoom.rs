@@ -0,0 +1,618 @@
+//! Deep zooms into the Mandelbrot set by perturbation
+//!
+//! Past a span of about 10⁻¹³ neighbouring pixels are no longer distinct in
+//! `f64`. A [`DeepZoom`] keeps its centre as a [`BigFixed`] and iterates only
+//! that point at high precision, as the reference orbit Zₙ. Every other pixel
+//! follows its offset from the reference, δzₙ₊₁ = (2Zₙ + δzₙ)·δzₙ + δc, which
+//! stays small enough for `f64`.
+//!
+//! Two things keep this fast and correct:
+//!
+//! - A cubic series in δc approximates δzₙ for the whole view over the first
+//!   iterations, so pixels start part-way along the reference. Probe pixels
+//!   at the edges of the view check how far the series can be trusted.
+//! - When a pixel's orbit passes closer to 0 than to the reference, its delta
+//!   loses precision (a glitch). The delta is then rebased onto the start of
+//!   the reference, as is done when the reference escapes first.
+
+use std::cmp::Ordering;
+use std::ops::{Add, Mul, Neg, Sub};
+
+use wasm_bindgen::prelude::*;
+
+use crate::{
+    Diagnostic, ErrorKind,
+    fractal::{Channels, Complex, ESCAPE_RADIUS, EscapeGrid, Sample, check_size},
+};
+
+/// The narrowest span a deep zoom can render, kept clear of `f64` underflow
+pub const MIN_SPAN: f64 = 1e-290;
+
+/// How far the series approximation may stray from perturbed probe orbits,
+/// relative to the delta
+const SERIES_TOLERANCE: f64 = 1e-6;
+
+/// A signed fixed-point number of arbitrary precision
+///
+/// The magnitude is kept in 64-bit limbs, least significant first; the last
+/// limb is the integer part and the others are the fraction. Operands of
+/// arithmetic must have the same number of fraction limbs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BigFixed {
+    negative: bool,
+    limbs: Vec<u64>,
+}
+
+impl BigFixed {
+    /// Zero, with `frac_limbs` limbs of fraction
+    pub fn zero(frac_limbs: usize) -> BigFixed {
+        BigFixed {
+            negative: false,
+            limbs: vec![0; frac_limbs + 1],
+        }
+    }
+
+    /// `x` exactly, as far as `frac_limbs` limbs of fraction allow
+    ///
+    /// `x` must be finite and smaller than 2⁶³ in magnitude.
+    pub fn from_f64(x: f64, frac_limbs: usize) -> BigFixed {
+        let mut out = BigFixed::zero(frac_limbs);
+        if x == 0.0 || !x.is_finite() {
+            return out;
+        }
+        out.negative = x < 0.0;
+        let bits = x.abs().to_bits();
+        let (field, mantissa) = ((bits >> 52) as i64, bits & ((1 << 52) - 1));
+        // |x| = mantissa · 2^exponent
+        let (mantissa, exponent) = match field {
+            0 => (mantissa, -1074),
+            _ => (mantissa | 1 << 52, field - 1075),
+        };
+        let shift = exponent + 64 * frac_limbs as i64;
+        let (mantissa, shift) = match shift {
+            ..0 => (mantissa.checked_shr((-shift) as u32).unwrap_or(0), 0),
+            _ => (mantissa, shift as usize),
+        };
+        let wide = (mantissa as u128) << (shift % 64);
+        let limb = shift / 64;
+        out.limbs[limb] = wide as u64;
+        if let Some(next) = out.limbs.get_mut(limb + 1) {
+            *next = (wide >> 64) as u64;
+        }
+        out.normalize()
+    }
+
+    /// Parse a decimal such as `-0.75`, `1.5e-30` or `+2`
+    pub fn parse(s: &str, frac_limbs: usize) -> Result<BigFixed, Diagnostic> {
+        let bad = || {
+            Diagnostic::new(
+                ErrorKind::Argument,
+                format!("`{s}` is not a decimal number"),
+            )
+        };
+        let text = s.trim();
+        let (negative, text) = match text.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, text.strip_prefix('+').unwrap_or(text)),
+        };
+        let (mantissa, exponent) = match text.split_once(['e', 'E']) {
+            Some((m, e)) => (m, e.parse::<i32>().map_err(|_| bad())?),
+            None => (text, 0),
+        };
+        let (whole, fraction) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+        let digits: Vec<u8> = whole.bytes().chain(fraction.bytes()).collect();
+        if digits.is_empty() || !digits.iter().all(u8::is_ascii_digit) {
+            return Err(bad());
+        }
+        let digits: Vec<u64> = digits.iter().map(|d| u64::from(d - b'0')).collect();
+        // Index into `digits` of the first digit after the decimal point
+        let point = whole.len() as i64 + i64::from(exponent);
+        let split = point.clamp(0, digits.len() as i64) as usize;
+
+        let mut out = BigFixed::zero(frac_limbs);
+        // Digits further out than this are below the last limb
+        let zeros = (-point).clamp(0, 20 * (frac_limbs as i64 + 1)) as usize;
+        let fraction = digits[split..].iter().copied().rev();
+        for digit in fraction.chain(std::iter::repeat_n(0, zeros)) {
+            out.limbs[frac_limbs] = digit;
+            out.div_small(10);
+        }
+        let trailing = std::iter::repeat_n(0, (point - digits.len() as i64).max(0) as usize);
+        let mut integer = 0u64;
+        for digit in digits[..split].iter().copied().chain(trailing) {
+            integer = integer
+                .checked_mul(10)
+                .and_then(|n| n.checked_add(digit))
+                .ok_or_else(|| {
+                    Diagnostic::new(ErrorKind::Argument, format!("`{s}` is too large"))
+                })?;
+        }
+        out.limbs[frac_limbs] = integer;
+        out.negative = negative;
+        Ok(out.normalize())
+    }
+
+    /// The nearest `f64`, give or take rounding in the last bit
+    pub fn to_f64(&self) -> f64 {
+        let frac = self.frac_limbs() as i32;
+        let magnitude: f64 = (self.limbs.iter().enumerate())
+            .map(|(i, &limb)| limb as f64 * 2f64.powi(64 * (i as i32 - frac)))
+            .sum();
+        if self.negative { -magnitude } else { magnitude }
+    }
+
+    /// Decimal digits, rounded to at most `digits` places after the point
+    pub fn to_decimal(&self, digits: usize) -> String {
+        let mut fraction = self.limbs.clone();
+        let mut integer = fraction.pop().unwrap_or(0);
+        let mut places = Vec::with_capacity(digits + 1);
+        for _ in 0..=digits {
+            let mut carry = 0u128;
+            for limb in &mut fraction {
+                let t = u128::from(*limb) * 10 + carry;
+                *limb = t as u64;
+                carry = t >> 64;
+            }
+            places.push(carry as u8);
+        }
+        // Round half up on the extra place
+        if places.pop().is_some_and(|d| d >= 5) {
+            match places.iter().rposition(|&d| d < 9) {
+                Some(i) => {
+                    places[i] += 1;
+                    places[i + 1..].fill(0);
+                }
+                None => {
+                    places.fill(0);
+                    integer += 1;
+                }
+            }
+        }
+        while places.last() == Some(&0) {
+            places.pop();
+        }
+        let mut out = String::new();
+        if self.negative && (integer != 0 || !places.is_empty()) {
+            out.push('-');
+        }
+        out.push_str(&integer.to_string());
+        if !places.is_empty() {
+            out.push('.');
+            out.extend(places.iter().map(|&d| char::from(b'0' + d)));
+        }
+        out
+    }
+
+    /// Limbs of fraction
+    pub fn frac_limbs(&self) -> usize {
+        self.limbs.len() - 1
+    }
+
+    /// This number with `frac_limbs` limbs of fraction, truncating if fewer
+    pub fn with_precision(&self, frac_limbs: usize) -> BigFixed {
+        let current = self.frac_limbs();
+        let limbs = if frac_limbs >= current {
+            let mut limbs = vec![0; frac_limbs - current];
+            limbs.extend_from_slice(&self.limbs);
+            limbs
+        } else {
+            self.limbs[current - frac_limbs..].to_vec()
+        };
+        BigFixed {
+            negative: self.negative,
+            limbs,
+        }
+        .normalize()
+    }
+
+    fn div_small(&mut self, d: u64) {
+        let mut rem = 0u128;
+        for limb in self.limbs.iter_mut().rev() {
+            let cur = (rem << 64) | u128::from(*limb);
+            *limb = (cur / u128::from(d)) as u64;
+            rem = cur % u128::from(d);
+        }
+    }
+
+    /// Zero is never negative
+    fn normalize(mut self) -> BigFixed {
+        if self.limbs.iter().all(|&limb| limb == 0) {
+            self.negative = false;
+        }
+        self
+    }
+
+    fn cmp_magnitude(&self, other: &BigFixed) -> Ordering {
+        self.limbs.iter().rev().cmp(other.limbs.iter().rev())
+    }
+}
+
+impl Neg for &BigFixed {
+    type Output = BigFixed;
+
+    fn neg(self) -> BigFixed {
+        BigFixed {
+            negative: !self.negative,
+            limbs: self.limbs.clone(),
+        }
+        .normalize()
+    }
+}
+
+impl Add for &BigFixed {
+    type Output = BigFixed;
+
+    fn add(self, other: &BigFixed) -> BigFixed {
+        debug_assert_eq!(self.limbs.len(), other.limbs.len());
+        let mut limbs = Vec::with_capacity(self.limbs.len());
+        let negative = if self.negative == other.negative {
+            let mut carry = false;
+            for (&a, &b) in self.limbs.iter().zip(&other.limbs) {
+                let (sum, c1) = a.overflowing_add(b);
+                let (sum, c2) = sum.overflowing_add(u64::from(carry));
+                limbs.push(sum);
+                carry = c1 || c2;
+            }
+            self.negative
+        } else {
+            // Subtract the smaller magnitude from the larger, keeping the larger's sign
+            let (big, small) = match self.cmp_magnitude(other) {
+                Ordering::Less => (other, self),
+                _ => (self, other),
+            };
+            let mut borrow = false;
+            for (&a, &b) in big.limbs.iter().zip(&small.limbs) {
+                let (diff, b1) = a.overflowing_sub(b);
+                let (diff, b2) = diff.overflowing_sub(u64::from(borrow));
+                limbs.push(diff);
+                borrow = b1 || b2;
+            }
+            big.negative
+        };
+        BigFixed { negative, limbs }.normalize()
+    }
+}
+
+impl Sub for &BigFixed {
+    type Output = BigFixed;
+
+    fn sub(self, other: &BigFixed) -> BigFixed {
+        self + &-other
+    }
+}
+
+impl Mul for &BigFixed {
+    type Output = BigFixed;
+
+    /// The product, truncated towards zero
+    fn mul(self, other: &BigFixed) -> BigFixed {
+        debug_assert_eq!(self.limbs.len(), other.limbs.len());
+        let n = self.limbs.len();
+        let mut wide = vec![0u64; 2 * n];
+        for (i, &a) in self.limbs.iter().enumerate() {
+            if a == 0 {
+                continue;
+            }
+            let mut carry = 0u128;
+            for (j, &b) in other.limbs.iter().enumerate() {
+                let t = u128::from(a) * u128::from(b) + u128::from(wide[i + j]) + carry;
+                wide[i + j] = t as u64;
+                carry = t >> 64;
+            }
+            wide[i + n] = carry as u64;
+        }
+        let frac = n - 1;
+        BigFixed {
+            negative: self.negative != other.negative,
+            limbs: wide[frac..frac + n].to_vec(),
+        }
+        .normalize()
+    }
+}
+
+/// A view of the Mandelbrot set whose centre is known to arbitrary precision
+#[wasm_bindgen]
+#[derive(Debug, Clone)]
+pub struct DeepZoom {
+    center_re: BigFixed,
+    center_im: BigFixed,
+    span_re: f64,
+    pub width: u32,
+    pub height: u32,
+    /// Iterations before a point is taken to be in the set
+    pub max_iter: u32,
+}
+
+/// What a deep zoom render did, for display and tuning
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DeepStats {
+    /// Points in the reference orbit, including z₀
+    pub reference_length: u32,
+    /// Iterations every pixel skipped by series approximation
+    pub skipped: u32,
+    /// Times a pixel's delta was rebased onto the start of the reference
+    pub rebases: u32,
+    /// Bits of fraction the reference orbit was computed with
+    pub precision_bits: u32,
+}
+
+#[wasm_bindgen]
+impl DeepZoom {
+    /// A view centred on the decimals `center_re + center_im·i`
+    #[wasm_bindgen(constructor)]
+    pub fn new(
+        center_re: &str,
+        center_im: &str,
+        span_re: f64,
+        width: u32,
+        height: u32,
+        max_iter: u32,
+    ) -> Result<DeepZoom, Diagnostic> {
+        check_span(span_re)?;
+        check_size(width, height)?;
+        let frac = precision(span_re, width);
+        Ok(DeepZoom {
+            center_re: BigFixed::parse(center_re, frac)?,
+            center_im: BigFixed::parse(center_im, frac)?,
+            span_re,
+            width,
+            height,
+            max_iter,
+        })
+    }
+
+    /// The real part of the centre, to a few digits finer than a pixel
+    #[wasm_bindgen(getter, js_name = centerRe)]
+    pub fn center_re(&self) -> String {
+        self.center_re.to_decimal(self.digits())
+    }
+
+    /// The imaginary part of the centre, to a few digits finer than a pixel
+    #[wasm_bindgen(getter, js_name = centerIm)]
+    pub fn center_im(&self) -> String {
+        self.center_im.to_decimal(self.digits())
+    }
+
+    #[wasm_bindgen(getter, js_name = spanRe)]
+    pub fn span_re(&self) -> f64 {
+        self.span_re
+    }
+
+    /// Move the centre to the point at `(x, y)` in pixel units and divide the span by `factor`
+    #[wasm_bindgen(js_name = zoomAt)]
+    pub fn zoom_at(&mut self, x: f64, y: f64, factor: f64) -> Result<(), Diagnostic> {
+        if !(x.is_finite() && y.is_finite() && factor.is_finite() && factor > 0.0) {
+            return Err(Diagnostic::new(
+                ErrorKind::Argument,
+                "Zooming needs a finite point and a positive factor",
+            ));
+        }
+        let span_re = self.span_re / factor;
+        check_span(span_re)?;
+        let offset = self.offset(x, y);
+        let frac = precision(span_re, self.width).max(self.center_re.frac_limbs());
+        self.center_re =
+            &self.center_re.with_precision(frac) + &BigFixed::from_f64(offset.re, frac);
+        self.center_im =
+            &self.center_im.with_precision(frac) + &BigFixed::from_f64(offset.im, frac);
+        self.span_re = span_re;
+        Ok(())
+    }
+
+    /// Render the view into `out`, with the `smooth` and `distance` channels if asked for
+    ///
+    /// Interior detection does not carry over to perturbed orbits, so the
+    /// `interior` channel is left out.
+    pub fn render(
+        &self,
+        channels: &Channels,
+        out: &mut EscapeGrid,
+    ) -> Result<DeepStats, Diagnostic> {
+        check_span(self.span_re)?;
+        check_size(self.width, self.height)?;
+        let frac = precision(self.span_re, self.width);
+        let (re, im) = (
+            self.center_re.with_precision(frac),
+            self.center_im.with_precision(frac),
+        );
+        let center = Complex::new(re.to_f64(), im.to_f64());
+        let orbit = reference_orbit(&re, &im, self.max_iter);
+
+        let probes = [(0.0, 0.0), (0.5, 0.0), (1.0, 0.0), (0.0, 0.5)]
+            .into_iter()
+            .chain([(1.0, 0.5), (0.0, 1.0), (0.5, 1.0), (1.0, 1.0)])
+            .map(|(fx, fy)| self.offset(fx * self.width as f64, fy * self.height as f64));
+        let corner = self.offset(0.0, 0.0);
+        let series = Series::new(&orbit, corner.re.hypot(corner.im), probes.collect());
+
+        let channels = Channels {
+            interior: false,
+            ..*channels
+        };
+        let mut rebases = 0u32;
+        out.fill(
+            (self.width, self.height),
+            self.max_iter,
+            channels,
+            |x, y| {
+                let dc = self.offset(x as f64 + 0.5, y as f64 + 0.5);
+                let (sample, r) = perturb(&orbit, &series, center, dc, self.max_iter, channels);
+                rebases = rebases.saturating_add(r);
+                sample
+            },
+        );
+        Ok(DeepStats {
+            reference_length: orbit.len() as u32,
+            skipped: series.skip as u32,
+            rebases,
+            precision_bits: 64 * frac as u32,
+        })
+    }
+}
+
+impl DeepZoom {
+    /// Offset from the centre of the point at `(x, y)` in pixel units
+    fn offset(&self, x: f64, y: f64) -> Complex {
+        let span_im = self.span_re * self.height as f64 / self.width as f64;
+        Complex::new(
+            (x / self.width as f64 - 0.5) * self.span_re,
+            -(y / self.height as f64 - 0.5) * span_im,
+        )
+    }
+
+    /// Decimal places that tell neighbouring pixels apart, and a few more
+    fn digits(&self) -> usize {
+        (self.width as f64 / self.span_re).log10().ceil().max(0.0) as usize + 3
+    }
+}
+
+fn check_span(span_re: f64) -> Result<(), Diagnostic> {
+    if span_re.is_finite() && span_re >= MIN_SPAN {
+        Ok(())
+    } else {
+        Err(Diagnostic::new(
+            ErrorKind::Argument,
+            format!("A deep zoom needs a finite span of at least {MIN_SPAN:e}"),
+        ))
+    }
+}
+
+/// Limbs of fraction that resolve a pixel, plus one to absorb rounding in the reference orbit
+fn precision(span_re: f64, width: u32) -> usize {
+    let bits = -(span_re / width as f64).log2();
+    ((bits.max(0.0) / 64.0).ceil() as usize + 1).max(2)
+}
+
+/// Zₙ at the centre, until it escapes or reaches `max_iter`
+///
+/// There are always at least two points, so deltas can be rebased onto the start.
+fn reference_orbit(re: &BigFixed, im: &BigFixed, max_iter: u32) -> Vec<Complex> {
+    let frac = re.frac_limbs();
+    let (mut x, mut y) = (BigFixed::zero(frac), BigFixed::zero(frac));
+    let mut orbit = vec![Complex::new(0.0, 0.0)];
+    while orbit.len() <= max_iter.max(1) as usize {
+        let xy = &x * &y;
+        (x, y) = (&(&(&x * &x) - &(&y * &y)) + re, &(&xy + &xy) + im);
+        let z = Complex::new(x.to_f64(), y.to_f64());
+        orbit.push(z);
+        if z.norm_sqr() > ESCAPE_RADIUS * ESCAPE_RADIUS {
+            break;
+        }
+    }
+    orbit
+}
+
+/// δz after `skip` steps as a cubic in u = δc / `scale`: a·u + b·u² + c·u³
+///
+/// Scaling by the view's half-diagonal keeps the coefficients within `f64`
+/// range and makes |u| ≤ 1 over the whole view.
+struct Series {
+    skip: usize,
+    coefficients: [Complex; 3],
+    scale: f64,
+}
+
+impl Series {
+    /// The longest series that agrees with perturbed orbits from each of `probes`
+    fn new(orbit: &[Complex], scale: f64, probes: Vec<Complex>) -> Series {
+        let zero = Complex::new(0.0, 0.0);
+        let mut history = vec![[zero; 3]];
+        for (n, &z) in orbit[..orbit.len() - 1].iter().enumerate() {
+            let [a, b, c] = history[n];
+            let two_z = z * 2.0;
+            let next = [
+                two_z * a + Complex::new(scale, 0.0),
+                two_z * b + a * a,
+                two_z * c + a * b * 2.0,
+            ];
+            let [a, b, c] = next.map(|k| k.norm_sqr().sqrt());
+            // Stop while the cubic term is still negligible and no pixel can have escaped
+            let bound = orbit[n + 1].norm_sqr().sqrt() + a + b + c;
+            if c.is_nan() || c > SERIES_TOLERANCE * a || bound > ESCAPE_RADIUS {
+                break;
+            }
+            history.push(next);
+        }
+
+        let deltas: Vec<Vec<Complex>> = (probes.iter())
+            .map(|&dc| {
+                let mut dz = zero;
+                let mut deltas = vec![dz];
+                for &z in &orbit[..history.len() - 1] {
+                    dz = (z * 2.0 + dz) * dz + dc;
+                    deltas.push(dz);
+                }
+                deltas
+            })
+            .collect();
+        let mut skip = history.len() - 1;
+        loop {
+            let series = Series {
+                skip,
+                coefficients: history[skip],
+                scale,
+            };
+            let agrees = probes.iter().zip(&deltas).all(|(&dc, deltas)| {
+                let error = (series.start(dc).0 - deltas[skip]).norm_sqr();
+                error <= SERIES_TOLERANCE * SERIES_TOLERANCE * deltas[skip].norm_sqr()
+            });
+            if agrees || skip == 0 {
+                return series;
+            }
+            skip /= 2;
+        }
+    }
+
+    /// δz and its derivative in δc after `skip` steps
+    fn start(&self, dc: Complex) -> (Complex, Complex) {
+        let [a, b, c] = self.coefficients;
+        let u = dc * (1.0 / self.scale);
+        let dz = ((c * u + b) * u + a) * u;
+        let der = ((c * u * 3.0 + b * 2.0) * u + a) * (1.0 / self.scale);
+        (dz, der)
+    }
+}
+
+/// Follow the pixel `center + dc` along the reference orbit
+///
+/// Returns its sample and how many times its delta was rebased.
+fn perturb(
+    orbit: &[Complex],
+    series: &Series,
+    center: Complex,
+    dc: Complex,
+    max_iter: u32,
+    channels: Channels,
+) -> (Sample, u32) {
+    let (mut dz, mut der) = series.start(dc);
+    let (mut m, mut n) = (series.skip, series.skip as u32);
+    let last = orbit.len() - 1;
+    let mut rebases = 0;
+    let mut z = orbit[m] + dz;
+    loop {
+        let norm = z.norm_sqr();
+        // Checked before the count, as an orbit can escape on the last step
+        if norm > ESCAPE_RADIUS * ESCAPE_RADIUS {
+            let c = center + dc;
+            return (Sample::escaped(n, z, der, (c, 1.0), channels), rebases);
+        }
+        if n >= max_iter {
+            let mut sample = Sample::bounded(max_iter);
+            sample.norm = norm;
+            return (sample, rebases);
+        }
+        // Near 0 the delta is as large as z itself and cancels badly against
+        // the reference, so follow z from the reference's start instead
+        if norm < dz.norm_sqr() || m == last {
+            (dz, m) = (z, 0);
+            rebases += 1;
+        }
+        if channels.distance {
+            der = z * der * 2.0 + Complex::new(1.0, 0.0);
+        }
+        dz = (orbit[m] * 2.0 + dz) * dz + dc;
+        (m, n) = (m + 1, n + 1);
+        z = orbit[m] + dz;
+    }
+}
@@ -0,0 +1,121 @@
+use uiua::{CodeSpan, InputSrc, Span, UiuaError, UiuaErrorKind};
+use wasm_bindgen::prelude::*;
+
+use crate::modules::MAIN_PATH;
+
+/// Which stage of a run produced an error
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// The source could not be parsed
+    Parse,
+    /// The source parsed but failed to compile (unknown names, bad signatures, ...)
+    Compile,
+    /// A call argument could not be converted to a Uiua value
+    Argument,
+    /// Execution failed, including failed `⍤` assertions
+    Runtime,
+    /// Execution exceeded its time limit or instruction budget
+    Timeout,
+    /// Execution was stopped through a cancellation token
+    Cancelled,
+    /// Execution finished but left nothing on the stack
+    Pop,
+    /// The result was a value type we cannot convert
+    UnsupportedValue,
+}
+
+/// A location in Uiua source, with 1-based lines and columns
+#[wasm_bindgen(getter_with_clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceSpan {
+    /// File the span points into, or `None` for the module that was run
+    pub file: Option<String>,
+    pub line: u32,
+    pub column: u32,
+    pub end_line: u32,
+    pub end_column: u32,
+}
+
+/// A structured error that the frontend can show next to the source
+#[wasm_bindgen(getter_with_clone)]
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub kind: ErrorKind,
+    pub message: String,
+    pub span: Option<SourceSpan>,
+}
+
+impl Diagnostic {
+    pub fn new(kind: ErrorKind, message: impl Into<String>) -> Self {
+        Diagnostic {
+            kind,
+            message: message.into(),
+            span: None,
+        }
+    }
+
+    /// Convert a Uiua error raised during `stage`
+    ///
+    /// Parse errors are always reported as [`ErrorKind::Parse`] whatever the stage.
+    pub fn from_uiua(stage: ErrorKind, err: &UiuaError) -> Self {
+        match &*err.kind {
+            UiuaErrorKind::Parse(errors, _) => {
+                let message = errors
+                    .iter()
+                    .map(|e| e.value.to_string())
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                Diagnostic {
+                    kind: ErrorKind::Parse,
+                    message,
+                    span: errors.first().map(|e| SourceSpan::from(&e.span)),
+                }
+            }
+            UiuaErrorKind::Run { message, .. } => Diagnostic {
+                kind: stage,
+                message: message.value.clone(),
+                span: SourceSpan::from_span(&message.span),
+            },
+            UiuaErrorKind::Throw(value, span, _) => Diagnostic {
+                kind: stage,
+                message: value.format(),
+                span: SourceSpan::from_span(span),
+            },
+            UiuaErrorKind::Timeout(..) => Diagnostic::new(ErrorKind::Timeout, err.to_string()),
+            UiuaErrorKind::Interrupted => Diagnostic::new(ErrorKind::Cancelled, err.to_string()),
+            _ => Diagnostic::new(stage, err.to_string()),
+        }
+    }
+}
+
+impl SourceSpan {
+    fn from_span(span: &Span) -> Option<Self> {
+        match span {
+            Span::Code(code) => Some(code.into()),
+            Span::Builtin => None,
+        }
+    }
+}
+
+impl From<&CodeSpan> for SourceSpan {
+    fn from(span: &CodeSpan) -> Self {
+        // Spans produced by macros point at the generated code; report the call site instead
+        if let InputSrc::Macro(site) = &span.src {
+            return (&**site).into();
+        }
+        let file = match &span.src {
+            InputSrc::File(path) if **path != *MAIN_PATH => {
+                Some(path.to_string_lossy().into_owned())
+            }
+            _ => None,
+        };
+        SourceSpan {
+            file,
+            line: span.start.line.into(),
+            column: span.start.col.into(),
+            end_line: span.end.line.into(),
+            end_column: span.end.col.into(),
+        }
+    }
+}
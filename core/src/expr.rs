@@ -0,0 +1,570 @@
+//! A small math expression language for maps typed in by the user
+//!
+//! Expressions use `+ - * / ^`, parentheses, numbers, the state variables
+//! (`x`, `y`), the map's parameters, the constant `pi` and the functions in
+//! [`Func`]. For expressions written as JavaScript, `**` is a synonym for `^`
+//! and functions and constants may be written `Math.sin`, `Math.PI` or `Math.E`.
+//! They parse once into an [`Expr`] and evaluate over any [`Scalar`]: plain
+//! `f64` for values, or [`Dual`] numbers for exact first derivatives.
+
+use std::ops::{Add, Div, Mul, Neg, Sub};
+
+use crate::{Diagnostic, ErrorKind, SourceSpan};
+
+/// A parsed expression
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Num(f64),
+    /// The state variable at this index, e.g. 0 for `x`
+    Var(usize),
+    /// The parameter at this index
+    Param(usize),
+    Neg(Box<Expr>),
+    Binary(BinOp, Box<Expr>, Box<Expr>),
+    Call(Func, Box<Expr>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Pow,
+}
+
+/// A function of one argument, named as in JavaScript's `Math`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Func {
+    Sin,
+    Cos,
+    Tan,
+    Asin,
+    Acos,
+    Atan,
+    Sinh,
+    Cosh,
+    Tanh,
+    Exp,
+    /// Natural logarithm
+    Log,
+    Sqrt,
+    Abs,
+}
+
+impl Func {
+    const ALL: [(&'static str, Func); 13] = [
+        ("sin", Func::Sin),
+        ("cos", Func::Cos),
+        ("tan", Func::Tan),
+        ("asin", Func::Asin),
+        ("acos", Func::Acos),
+        ("atan", Func::Atan),
+        ("sinh", Func::Sinh),
+        ("cosh", Func::Cosh),
+        ("tanh", Func::Tanh),
+        ("exp", Func::Exp),
+        ("log", Func::Log),
+        ("sqrt", Func::Sqrt),
+        ("abs", Func::Abs),
+    ];
+
+    pub fn apply(self, x: f64) -> f64 {
+        match self {
+            Func::Sin => x.sin(),
+            Func::Cos => x.cos(),
+            Func::Tan => x.tan(),
+            Func::Asin => x.asin(),
+            Func::Acos => x.acos(),
+            Func::Atan => x.atan(),
+            Func::Sinh => x.sinh(),
+            Func::Cosh => x.cosh(),
+            Func::Tanh => x.tanh(),
+            Func::Exp => x.exp(),
+            Func::Log => x.ln(),
+            Func::Sqrt => x.sqrt(),
+            Func::Abs => x.abs(),
+        }
+    }
+
+    /// The derivative at `x`, given `y = self.apply(x)`
+    fn slope(self, x: f64, y: f64) -> f64 {
+        match self {
+            Func::Sin => x.cos(),
+            Func::Cos => -x.sin(),
+            Func::Tan => 1.0 + y * y,
+            Func::Asin => 1.0 / (1.0 - x * x).sqrt(),
+            Func::Acos => -1.0 / (1.0 - x * x).sqrt(),
+            Func::Atan => 1.0 / (1.0 + x * x),
+            Func::Sinh => x.cosh(),
+            Func::Cosh => x.sinh(),
+            Func::Tanh => 1.0 - y * y,
+            Func::Exp => y,
+            Func::Log => 1.0 / x,
+            Func::Sqrt => 0.5 / y,
+            // Take the derivative at 0 as 0, the midpoint of the one-sided slopes
+            Func::Abs if x == 0.0 => 0.0,
+            Func::Abs => x.signum(),
+        }
+    }
+
+    fn named(name: &str) -> Option<Func> {
+        (Self::ALL.iter())
+            .find(|(n, _)| *n == name)
+            .map(|&(_, f)| f)
+    }
+
+    /// Whether `name` is reserved for a function
+    pub fn is_reserved(name: &str) -> bool {
+        Self::named(name).is_some()
+    }
+}
+
+/// Numbers an [`Expr`] can be evaluated over
+pub trait Scalar:
+    Copy
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + Div<Output = Self>
+    + Neg<Output = Self>
+{
+    fn constant(value: f64) -> Self;
+    fn pow(self, exponent: Self) -> Self;
+    fn call(self, func: Func) -> Self;
+}
+
+impl Scalar for f64 {
+    fn constant(value: f64) -> Self {
+        value
+    }
+    fn pow(self, exponent: Self) -> Self {
+        powf(self, exponent)
+    }
+    fn call(self, func: Func) -> Self {
+        func.apply(self)
+    }
+}
+
+/// `base ^ exponent`, exact for small integer exponents and defined for negative bases
+fn powf(base: f64, exponent: f64) -> f64 {
+    if exponent.fract() == 0.0 && exponent.abs() <= i32::MAX as f64 {
+        base.powi(exponent as i32)
+    } else {
+        base.powf(exponent)
+    }
+}
+
+/// A forward-mode dual number: a value and its gradient with respect to `(x, y)`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Dual {
+    pub re: f64,
+    pub grad: [f64; 2],
+}
+
+impl Dual {
+    /// The state variable `index` as a dual number, seeded with a unit gradient
+    pub fn variable(value: f64, index: usize) -> Self {
+        let mut grad = [0.0; 2];
+        grad[index] = 1.0;
+        Dual { re: value, grad }
+    }
+
+    /// Apply a function with value `re` and derivative `slope` at `self.re`
+    fn chain(self, re: f64, slope: f64) -> Self {
+        Dual {
+            re,
+            grad: self.grad.map(|d| slope * d),
+        }
+    }
+}
+
+impl Add for Dual {
+    type Output = Dual;
+    fn add(self, rhs: Dual) -> Dual {
+        Dual {
+            re: self.re + rhs.re,
+            grad: [self.grad[0] + rhs.grad[0], self.grad[1] + rhs.grad[1]],
+        }
+    }
+}
+
+impl Sub for Dual {
+    type Output = Dual;
+    fn sub(self, rhs: Dual) -> Dual {
+        self + -rhs
+    }
+}
+
+// The product and quotient rules mix operators by design
+#[allow(clippy::suspicious_arithmetic_impl)]
+impl Mul for Dual {
+    type Output = Dual;
+    fn mul(self, rhs: Dual) -> Dual {
+        Dual {
+            re: self.re * rhs.re,
+            grad: [0, 1].map(|i| self.grad[i] * rhs.re + self.re * rhs.grad[i]),
+        }
+    }
+}
+
+#[allow(clippy::suspicious_arithmetic_impl)]
+impl Div for Dual {
+    type Output = Dual;
+    fn div(self, rhs: Dual) -> Dual {
+        let re = self.re / rhs.re;
+        Dual {
+            re,
+            grad: [0, 1].map(|i| (self.grad[i] - re * rhs.grad[i]) / rhs.re),
+        }
+    }
+}
+
+impl Neg for Dual {
+    type Output = Dual;
+    fn neg(self) -> Dual {
+        Dual {
+            re: -self.re,
+            grad: self.grad.map(|d| -d),
+        }
+    }
+}
+
+impl Scalar for Dual {
+    fn constant(value: f64) -> Self {
+        Dual {
+            re: value,
+            grad: [0.0; 2],
+        }
+    }
+
+    fn pow(self, exponent: Self) -> Self {
+        let re = powf(self.re, exponent.re);
+        if exponent.grad == [0.0; 2] {
+            // A constant exponent works for negative bases too; x^0 is flat
+            // even at 0, where 0 · 0^-1 would be NaN
+            let slope = match exponent.re {
+                0.0 => 0.0,
+                b => b * powf(self.re, b - 1.0),
+            };
+            return self.chain(re, slope);
+        }
+        // d(a^b) = a^b (b' ln a + b a' / a)
+        let ln = self.re.ln();
+        Dual {
+            re,
+            grad: [0, 1]
+                .map(|i| re * (exponent.grad[i] * ln + exponent.re * self.grad[i] / self.re)),
+        }
+    }
+
+    fn call(self, func: Func) -> Self {
+        let re = func.apply(self.re);
+        self.chain(re, func.slope(self.re, re))
+    }
+}
+
+impl Expr {
+    /// Parse `source`, resolving names against the state `variables` and `params`
+    ///
+    /// Errors are [`ErrorKind::Parse`] diagnostics whose span gives the column
+    /// of the problem on line 1.
+    pub fn parse(source: &str, variables: &[&str], params: &[&str]) -> Result<Expr, Diagnostic> {
+        let tokens = tokenize(source)?;
+        let mut parser = Parser {
+            tokens: &tokens,
+            pos: 0,
+            end: source.chars().count() as u32 + 1,
+            variables,
+            params,
+        };
+        let expr = parser.sum()?;
+        match parser.peek() {
+            None => Ok(expr),
+            Some(token) => Err(token.error(format!("Unexpected {}", token.kind))),
+        }
+    }
+
+    pub fn eval<T: Scalar>(&self, state: &[T], params: &[f64]) -> T {
+        match self {
+            Expr::Num(n) => T::constant(*n),
+            Expr::Var(i) => state[*i],
+            Expr::Param(i) => T::constant(params[*i]),
+            Expr::Neg(e) => -e.eval(state, params),
+            Expr::Binary(op, a, b) => {
+                let (a, b) = (a.eval(state, params), b.eval(state, params));
+                match op {
+                    BinOp::Add => a + b,
+                    BinOp::Sub => a - b,
+                    BinOp::Mul => a * b,
+                    BinOp::Div => a / b,
+                    BinOp::Pow => a.pow(b),
+                }
+            }
+            Expr::Call(func, e) => e.eval(state, params).call(*func),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum TokenKind {
+    Num(f64),
+    Ident(String),
+    Op(&'static str),
+    Open,
+    Close,
+}
+
+impl std::fmt::Display for TokenKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            TokenKind::Num(n) => write!(f, "number {n}"),
+            TokenKind::Ident(name) => write!(f, "`{name}`"),
+            TokenKind::Op(c) => write!(f, "`{c}`"),
+            TokenKind::Open => write!(f, "`(`"),
+            TokenKind::Close => write!(f, "`)`"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Token {
+    kind: TokenKind,
+    /// 1-based columns, end exclusive
+    column: u32,
+    end: u32,
+}
+
+impl Token {
+    fn error(&self, message: impl Into<String>) -> Diagnostic {
+        parse_error(self.column, self.end, message)
+    }
+}
+
+fn parse_error(column: u32, end_column: u32, message: impl Into<String>) -> Diagnostic {
+    Diagnostic {
+        span: Some(SourceSpan {
+            file: None,
+            line: 1,
+            column,
+            end_line: 1,
+            end_column,
+        }),
+        ..Diagnostic::new(ErrorKind::Parse, message)
+    }
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>, Diagnostic> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let start = i;
+        let kind = if c.is_whitespace() {
+            i += 1;
+            continue;
+        } else if c.is_ascii_digit() || c == '.' {
+            while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                i += 1;
+            }
+            // Exponent, as in 1e-3
+            if i < chars.len() && matches!(chars[i], 'e' | 'E') {
+                let sign = matches!(chars.get(i + 1), Some('+' | '-')) as usize;
+                if chars.get(i + 1 + sign).is_some_and(|c| c.is_ascii_digit()) {
+                    i += 1 + sign;
+                    while i < chars.len() && chars[i].is_ascii_digit() {
+                        i += 1;
+                    }
+                }
+            }
+            let text: String = chars[start..i].iter().collect();
+            let n = text.parse().map_err(|_| {
+                parse_error(
+                    start as u32 + 1,
+                    i as u32 + 1,
+                    format!("Invalid number `{text}`"),
+                )
+            })?;
+            TokenKind::Num(n)
+        } else if c.is_alphabetic() || c == '_' {
+            let ident = |i: &mut usize| {
+                while *i < chars.len() && (chars[*i].is_alphanumeric() || chars[*i] == '_') {
+                    *i += 1;
+                }
+            };
+            ident(&mut i);
+            // Keep `Math.sin` together so it resolves as one name
+            if chars[start..i] == ['M', 'a', 't', 'h'] && chars.get(i) == Some(&'.') {
+                i += 1;
+                ident(&mut i);
+            }
+            TokenKind::Ident(chars[start..i].iter().collect())
+        } else if c == '*' && chars.get(i + 1) == Some(&'*') {
+            i += 2;
+            TokenKind::Op("**")
+        } else {
+            i += 1;
+            match c {
+                '+' => TokenKind::Op("+"),
+                '-' => TokenKind::Op("-"),
+                '*' => TokenKind::Op("*"),
+                '/' => TokenKind::Op("/"),
+                '^' => TokenKind::Op("^"),
+                '(' => TokenKind::Open,
+                ')' => TokenKind::Close,
+                _ => {
+                    return Err(parse_error(
+                        start as u32 + 1,
+                        i as u32 + 1,
+                        format!("Unexpected character `{c}`"),
+                    ));
+                }
+            }
+        };
+        tokens.push(Token {
+            kind,
+            column: start as u32 + 1,
+            end: i as u32 + 1,
+        });
+    }
+    Ok(tokens)
+}
+
+/// Recursive descent over the tokens, one method per precedence level
+struct Parser<'a> {
+    tokens: &'a [Token],
+    pos: usize,
+    /// Column just past the end of the source, for errors at the end
+    end: u32,
+    variables: &'a [&'a str],
+    params: &'a [&'a str],
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    /// Consume the next token if it is one of the operators `ops`
+    fn eat_op(&mut self, ops: &[&str]) -> Option<&'static str> {
+        match self.peek()?.kind {
+            TokenKind::Op(op) if ops.contains(&op) => {
+                self.pos += 1;
+                Some(op)
+            }
+            _ => None,
+        }
+    }
+
+    fn binary(op: BinOp, a: Expr, b: Expr) -> Expr {
+        Expr::Binary(op, Box::new(a), Box::new(b))
+    }
+
+    /// `product (('+' | '-') product)*`
+    fn sum(&mut self) -> Result<Expr, Diagnostic> {
+        let mut expr = self.product()?;
+        while let Some(op) = self.eat_op(&["+", "-"]) {
+            let op = if op == "+" { BinOp::Add } else { BinOp::Sub };
+            expr = Self::binary(op, expr, self.product()?);
+        }
+        Ok(expr)
+    }
+
+    /// `unary (('*' | '/') unary)*`
+    fn product(&mut self) -> Result<Expr, Diagnostic> {
+        let mut expr = self.unary()?;
+        while let Some(op) = self.eat_op(&["*", "/"]) {
+            let op = if op == "*" { BinOp::Mul } else { BinOp::Div };
+            expr = Self::binary(op, expr, self.unary()?);
+        }
+        Ok(expr)
+    }
+
+    /// `('-' | '+') unary | power`, so that `-x^2` is `-(x^2)`
+    fn unary(&mut self) -> Result<Expr, Diagnostic> {
+        match self.eat_op(&["-", "+"]) {
+            Some("-") => Ok(Expr::Neg(Box::new(self.unary()?))),
+            Some(_) => self.unary(),
+            None => self.power(),
+        }
+    }
+
+    /// `atom (('^' | '**') unary)?`, right associative
+    fn power(&mut self) -> Result<Expr, Diagnostic> {
+        let base = self.atom()?;
+        match self.eat_op(&["^", "**"]) {
+            Some(_) => Ok(Self::binary(BinOp::Pow, base, self.unary()?)),
+            None => Ok(base),
+        }
+    }
+
+    /// A number, a name, a function call or a parenthesized expression
+    fn atom(&mut self) -> Result<Expr, Diagnostic> {
+        let Some(token) = self.next() else {
+            return Err(parse_error(self.end, self.end, "Expected an expression"));
+        };
+        match &token.kind {
+            TokenKind::Num(n) => Ok(Expr::Num(*n)),
+            TokenKind::Open => self.group(),
+            TokenKind::Ident(name) => {
+                let (bare, prefixed) = match name.strip_prefix("Math.") {
+                    Some(bare) => (bare, true),
+                    None => (name.as_str(), false),
+                };
+                if let Some(func) = Func::named(bare) {
+                    if !matches!(
+                        self.peek(),
+                        Some(Token {
+                            kind: TokenKind::Open,
+                            ..
+                        })
+                    ) {
+                        return Err(
+                            token.error(format!("`{name}` needs an argument in parentheses"))
+                        );
+                    }
+                    self.pos += 1;
+                    return Ok(Expr::Call(func, Box::new(self.group()?)));
+                }
+                if prefixed {
+                    return match bare {
+                        "PI" => Ok(Expr::Num(std::f64::consts::PI)),
+                        "E" => Ok(Expr::Num(std::f64::consts::E)),
+                        _ => Err(token.error(format!("Unsupported function `{name}`"))),
+                    };
+                }
+                if let Some(i) = self.variables.iter().position(|v| v == name) {
+                    return Ok(Expr::Var(i));
+                }
+                if let Some(i) = self.params.iter().position(|p| p == name) {
+                    return Ok(Expr::Param(i));
+                }
+                if name == "pi" {
+                    return Ok(Expr::Num(std::f64::consts::PI));
+                }
+                Err(token.error(format!("Unknown name `{name}`")))
+            }
+            kind => Err(token.error(format!("Expected an expression but found {kind}"))),
+        }
+    }
+
+    /// The rest of a parenthesized expression after `(`
+    fn group(&mut self) -> Result<Expr, Diagnostic> {
+        let expr = self.sum()?;
+        match self.next() {
+            Some(Token {
+                kind: TokenKind::Close,
+                ..
+            }) => Ok(expr),
+            Some(token) => Err(token.error(format!("Expected `)` but found {}", token.kind))),
+            None => Err(parse_error(self.end, self.end, "Expected `)`")),
+        }
+    }
+}
@@ -0,0 +1,135 @@
+//! Fixed points by Newton's method, classified by the eigenvalues of the Jacobian
+
+use wasm_bindgen::prelude::*;
+
+use crate::maps::{Jacobian, State, escaped};
+
+/// Newton steps before giving up on a starting guess
+const MAX_STEPS: u32 = 50;
+
+/// Eigenvalues this close to the unit circle count as neutral
+const NEUTRAL_TOLERANCE: f64 = 1e-9;
+
+/// How nearby orbits behave around a fixed point
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stability {
+    /// Every eigenvalue lies inside the unit circle
+    Attracting,
+    /// Every eigenvalue lies outside the unit circle
+    Repelling,
+    /// One eigenvalue inside the unit circle and one outside
+    Saddle,
+    /// An eigenvalue on the unit circle, so linearisation cannot decide
+    Neutral,
+}
+
+/// A fixed point with the eigenvalues of the Jacobian there
+#[wasm_bindgen(getter_with_clone)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct FixedPoint {
+    pub x: f64,
+    pub y: f64,
+    /// Real parts of the eigenvalues, one per dimension
+    pub eigen_re: Vec<f64>,
+    /// Imaginary parts of the eigenvalues, in the same order
+    pub eigen_im: Vec<f64>,
+    pub stability: Stability,
+}
+
+/// Solve `step(s) = s` by Newton's method from `guess`
+///
+/// Returns `None` if the iteration escapes, hits a singular `J - I` or does
+/// not converge.
+pub fn newton(
+    dimension: u32,
+    step: impl Fn(State) -> State,
+    jacobian: impl Fn(State) -> Jacobian,
+    guess: State,
+) -> Option<State> {
+    let mut s = guess;
+    if dimension == 1 {
+        s[1] = 0.0;
+    }
+    for _ in 0..MAX_STEPS {
+        let f = step(s);
+        let r = [f[0] - s[0], f[1] - s[1]];
+        let j = jacobian(s);
+        // Solve (J - I) δ = -r
+        let delta = if dimension == 1 {
+            [-r[0] / (j[0][0] - 1.0), 0.0]
+        } else {
+            let (a, b, c, d) = (j[0][0] - 1.0, j[0][1], j[1][0], j[1][1] - 1.0);
+            let det = a * d - b * c;
+            [(-r[0] * d + b * r[1]) / det, (c * r[0] - a * r[1]) / det]
+        };
+        s = [s[0] + delta[0], s[1] + delta[1]];
+        if escaped(s) || delta.iter().any(|d| !d.is_finite()) {
+            return None;
+        }
+        if delta[0].hypot(delta[1]) <= 1e-12 * (1.0 + s[0].hypot(s[1])) {
+            return Some(s);
+        }
+    }
+    None
+}
+
+/// The fixed point `state` with the eigenvalues of its Jacobian `j` and the stability they imply
+pub fn classify(dimension: u32, state: State, j: &Jacobian) -> FixedPoint {
+    let (eigen_re, eigen_im) = if dimension == 1 {
+        (vec![j[0][0]], vec![0.0])
+    } else {
+        let half_trace = (j[0][0] + j[1][1]) / 2.0;
+        let det = j[0][0] * j[1][1] - j[0][1] * j[1][0];
+        let disc = half_trace * half_trace - det;
+        if disc >= 0.0 {
+            let root = disc.sqrt();
+            (vec![half_trace + root, half_trace - root], vec![0.0, 0.0])
+        } else {
+            let root = (-disc).sqrt();
+            (vec![half_trace, half_trace], vec![root, -root])
+        }
+    };
+    let moduli = eigen_re.iter().zip(&eigen_im).map(|(re, im)| re.hypot(*im));
+    let (mut inside, mut outside) = (0, 0);
+    for m in moduli {
+        if m < 1.0 - NEUTRAL_TOLERANCE {
+            inside += 1;
+        } else if m > 1.0 + NEUTRAL_TOLERANCE {
+            outside += 1;
+        }
+    }
+    let stability = match (inside, outside) {
+        _ if inside + outside < eigen_re.len() => Stability::Neutral,
+        (_, 0) => Stability::Attracting,
+        (0, _) => Stability::Repelling,
+        _ => Stability::Saddle,
+    };
+    FixedPoint {
+        x: state[0],
+        y: state[1],
+        eigen_re,
+        eigen_im,
+        stability,
+    }
+}
+
+/// Run [`newton`] from every guess and classify the distinct fixed points found
+pub fn fixed_points(
+    dimension: u32,
+    step: impl Fn(State) -> State,
+    jacobian: impl Fn(State) -> Jacobian,
+    guesses: impl IntoIterator<Item = State>,
+) -> Vec<FixedPoint> {
+    let mut found: Vec<FixedPoint> = Vec::new();
+    for guess in guesses {
+        let Some(s) = newton(dimension, &step, &jacobian, guess) else {
+            continue;
+        };
+        let tol = 1e-8 * (1.0 + s[0].hypot(s[1]));
+        if !found.iter().any(|p| (p.x - s[0]).hypot(p.y - s[1]) <= tol) {
+            found.push(classify(dimension, s, &jacobian(s)));
+        }
+    }
+    found
+}
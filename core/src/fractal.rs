@@ -0,0 +1,588 @@
+//! Escape-time renderers for the Mandelbrot and Julia sets
+//!
+//! A [`Viewport`] mirrors the `Viewport` sketched in `NewProject.md`: the
+//! centre of the view, its width in the complex plane, and its size in
+//! pixels. Results go into an [`EscapeGrid`], which keeps its allocations
+//! across renders the way [`OutputBuffer`](crate::OutputBuffer) does, along
+//! with whichever optional [`Channels`] a render asks for.
+//!
+//! [`julia_boundary`] traces Julia sets as points instead, by the Modified
+//! Inverse Iteration Method.
+
+use wasm_bindgen::prelude::*;
+
+use crate::{Diagnostic, ErrorKind};
+
+/// Orbits are counted as escaped once |z| exceeds this
+pub const ESCAPE_RADIUS: f64 = 2.0;
+
+/// Escaped orbits are followed on to this radius for the smooth count and
+/// distance estimate, whose errors shrink as |z| grows
+const SMOOTH_ESCAPE_RADIUS: f64 = 256.0;
+
+/// Most steps spent following an escaped orbit out to [`SMOOTH_ESCAPE_RADIUS`]
+const MAX_EXTRA_STEPS: u32 = 64;
+
+/// An orbit that returns this close to an earlier point is taken to be periodic
+const PERIOD_TOLERANCE: f64 = 1e-12;
+
+/// Refuse renders with more pixels than this rather than exhaust wasm memory
+const MAX_PIXELS: u64 = 1 << 26;
+
+/// A complex number `re + im·i`
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Complex {
+    pub re: f64,
+    pub im: f64,
+}
+
+#[wasm_bindgen]
+impl Complex {
+    #[wasm_bindgen(constructor)]
+    pub fn new(re: f64, im: f64) -> Complex {
+        Complex { re, im }
+    }
+}
+
+impl Complex {
+    /// |z|²
+    pub fn norm_sqr(self) -> f64 {
+        self.re * self.re + self.im * self.im
+    }
+
+    /// The principal square root, with `re >= 0`
+    pub fn sqrt(self) -> Complex {
+        let r = self.re.hypot(self.im);
+        let re = ((r + self.re) / 2.0).sqrt();
+        let im = ((r - self.re) / 2.0).max(0.0).sqrt();
+        Complex::new(re, im.copysign(self.im))
+    }
+}
+
+impl std::ops::Add for Complex {
+    type Output = Complex;
+
+    fn add(self, w: Complex) -> Complex {
+        Complex::new(self.re + w.re, self.im + w.im)
+    }
+}
+
+impl std::ops::Sub for Complex {
+    type Output = Complex;
+
+    fn sub(self, w: Complex) -> Complex {
+        Complex::new(self.re - w.re, self.im - w.im)
+    }
+}
+
+impl std::ops::Mul for Complex {
+    type Output = Complex;
+
+    fn mul(self, w: Complex) -> Complex {
+        Complex::new(
+            self.re * w.re - self.im * w.im,
+            self.re * w.im + self.im * w.re,
+        )
+    }
+}
+
+impl std::ops::Mul<f64> for Complex {
+    type Output = Complex;
+
+    fn mul(self, k: f64) -> Complex {
+        Complex::new(self.re * k, self.im * k)
+    }
+}
+
+/// The part of the complex plane to render and the grid of pixels to sample it with
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Viewport {
+    pub center: Complex,
+    /// Width of the view along the real axis; the imaginary span follows from the aspect ratio
+    pub span_re: f64,
+    pub width: u32,
+    pub height: u32,
+    /// Iterations before a point is taken to be in the set
+    pub max_iter: u32,
+}
+
+#[wasm_bindgen]
+impl Viewport {
+    #[wasm_bindgen(constructor)]
+    pub fn new(center: Complex, span_re: f64, width: u32, height: u32, max_iter: u32) -> Viewport {
+        Viewport {
+            center,
+            span_re,
+            width,
+            height,
+            max_iter,
+        }
+    }
+
+    /// Height of the view along the imaginary axis, keeping pixels square
+    #[wasm_bindgen(getter)]
+    pub fn span_im(&self) -> f64 {
+        self.span_re * self.height as f64 / self.width as f64
+    }
+
+    /// The point at the centre of pixel `(x, y)`, counting rows down from the top
+    pub fn pixel(&self, x: u32, y: u32) -> Complex {
+        self.point(x as f64 + 0.5, y as f64 + 0.5)
+    }
+
+    /// The point at `(x, y)` in pixel units, measured from the top-left corner of the view
+    pub fn point(&self, x: f64, y: f64) -> Complex {
+        Complex {
+            re: self.center.re + (x / self.width as f64 - 0.5) * self.span_re,
+            im: self.center.im - (y / self.height as f64 - 0.5) * self.span_im(),
+        }
+    }
+}
+
+impl Viewport {
+    /// Index of the pixel containing `z`, row-major, or `None` outside the view
+    fn index_of(&self, z: Complex) -> Option<usize> {
+        let fx = ((z.re - self.center.re) / self.span_re + 0.5) * self.width as f64;
+        let fy = (0.5 - (z.im - self.center.im) / self.span_im()) * self.height as f64;
+        let inside = fx >= 0.0 && fy >= 0.0 && fx < self.width as f64 && fy < self.height as f64;
+        inside.then(|| fy as usize * self.width as usize + fx as usize)
+    }
+
+    pub(crate) fn check(&self) -> Result<(), Diagnostic> {
+        check_size(self.width, self.height)?;
+        let valid = self.center.re.is_finite()
+            && self.center.im.is_finite()
+            && self.span_re > 0.0
+            && self.span_re.is_finite();
+        if !valid {
+            return Err(Diagnostic::new(
+                ErrorKind::Argument,
+                "A viewport needs a finite centre and a positive span",
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Check that a `width` × `height` grid is neither empty nor too large to allocate
+pub(crate) fn check_size(width: u32, height: u32) -> Result<(), Diagnostic> {
+    let pixels = u64::from(width) * u64::from(height);
+    if pixels == 0 {
+        return Err(Diagnostic::new(
+            ErrorKind::Argument,
+            "A render needs at least one pixel",
+        ));
+    }
+    if pixels > MAX_PIXELS {
+        return Err(Diagnostic::new(
+            ErrorKind::Argument,
+            format!("A render of {pixels} pixels is too large"),
+        ));
+    }
+    Ok(())
+}
+
+/// Which escape-time fractal to render
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FractalKind {
+    Mandelbrot,
+    Julia,
+}
+
+/// Which channels a render fills in besides the counts and norms
+///
+/// Each one costs time or memory, so all are off by default.
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Channels {
+    /// Continuous iteration counts ν = n + 1 − log₂ log₂|z|, free of the banding of integer counts
+    pub smooth: bool,
+    /// Estimated distance from each exterior point to the set, from the derivative of the orbit
+    pub distance: bool,
+    /// Flags for points shown to lie inside the set, which also ends their iteration early
+    pub interior: bool,
+}
+
+#[wasm_bindgen]
+impl Channels {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Channels {
+        Channels::default()
+    }
+}
+
+/// Per-pixel results of an escape-time render, row-major from the top-left
+///
+/// Like [`OutputBuffer`](crate::OutputBuffer), the views returned to JS point
+/// into wasm memory and are only valid until the next call into the engine.
+/// The optional channels are empty unless the last render asked for them.
+#[wasm_bindgen]
+#[derive(Debug, Clone, Default)]
+pub struct EscapeGrid {
+    width: u32,
+    height: u32,
+    max_iter: u32,
+    counts: Vec<u32>,
+    norms: Vec<f64>,
+    smooth: Vec<f64>,
+    distance: Vec<f64>,
+    interior: Vec<u8>,
+}
+
+#[wasm_bindgen]
+impl EscapeGrid {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> EscapeGrid {
+        EscapeGrid::default()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// The iteration limit of the last render, which counts reach only inside the set
+    #[wasm_bindgen(getter, js_name = maxIter)]
+    pub fn max_iter(&self) -> u32 {
+        self.max_iter
+    }
+
+    /// Iterations before each orbit escaped, or `max_iter` if it never did
+    #[wasm_bindgen(js_name = counts)]
+    pub fn counts_view(&self) -> js_sys::Uint32Array {
+        // SAFETY: as for `OutputBuffer::view`
+        unsafe { js_sys::Uint32Array::view(&self.counts) }
+    }
+
+    /// |z|² after the last iteration computed for each orbit
+    #[wasm_bindgen(js_name = norms)]
+    pub fn norms_view(&self) -> js_sys::Float64Array {
+        // SAFETY: as for `OutputBuffer::view`
+        unsafe { js_sys::Float64Array::view(&self.norms) }
+    }
+
+    /// Continuous iteration counts, or `max_iter` for orbits that never escaped
+    #[wasm_bindgen(js_name = smooth)]
+    pub fn smooth_view(&self) -> js_sys::Float64Array {
+        // SAFETY: as for `OutputBuffer::view`
+        unsafe { js_sys::Float64Array::view(&self.smooth) }
+    }
+
+    /// Distance estimates, or 0 for orbits that never escaped
+    #[wasm_bindgen(js_name = distance)]
+    pub fn distance_view(&self) -> js_sys::Float64Array {
+        // SAFETY: as for `OutputBuffer::view`
+        unsafe { js_sys::Float64Array::view(&self.distance) }
+    }
+
+    /// 1 for points shown to lie inside the set, 0 otherwise
+    #[wasm_bindgen(js_name = interior)]
+    pub fn interior_view(&self) -> js_sys::Uint8Array {
+        // SAFETY: as for `OutputBuffer::view`
+        unsafe { js_sys::Uint8Array::view(&self.interior) }
+    }
+}
+
+impl EscapeGrid {
+    pub fn counts(&self) -> &[u32] {
+        &self.counts
+    }
+
+    pub fn norms(&self) -> &[f64] {
+        &self.norms
+    }
+
+    pub fn smooth(&self) -> &[f64] {
+        &self.smooth
+    }
+
+    pub fn distance(&self) -> &[f64] {
+        &self.distance
+    }
+
+    pub fn interior(&self) -> &[u8] {
+        &self.interior
+    }
+
+    /// Fill a `width` × `height` grid by tracing the orbit for `point(x, y)` at each cell
+    ///
+    /// The point is `c` for the Mandelbrot set and `z₀` for the Julia set of `julia_c`.
+    pub(crate) fn render(
+        &mut self,
+        kind: FractalKind,
+        julia_c: Complex,
+        size: (u32, u32),
+        max_iter: u32,
+        channels: Channels,
+        point: impl Fn(u32, u32) -> Complex,
+    ) {
+        let origin = Complex::new(0.0, 0.0);
+        self.fill(size, max_iter, channels, |x, y| match kind {
+            FractalKind::Mandelbrot => trace(origin, point(x, y), true, max_iter, channels),
+            FractalKind::Julia => trace(point(x, y), julia_c, false, max_iter, channels),
+        });
+    }
+
+    /// Fill a `width` × `height` grid with the orbit `sample(x, y)` of each cell
+    pub(crate) fn fill(
+        &mut self,
+        (width, height): (u32, u32),
+        max_iter: u32,
+        channels: Channels,
+        mut sample: impl FnMut(u32, u32) -> Sample,
+    ) {
+        self.width = width;
+        self.height = height;
+        self.max_iter = max_iter;
+        self.counts.clear();
+        self.norms.clear();
+        self.smooth.clear();
+        self.distance.clear();
+        self.interior.clear();
+        for y in 0..height {
+            for x in 0..width {
+                let sample = sample(x, y);
+                self.counts.push(sample.count);
+                self.norms.push(sample.norm);
+                if channels.smooth {
+                    self.smooth.push(sample.smooth);
+                }
+                if channels.distance {
+                    self.distance.push(sample.distance);
+                }
+                if channels.interior {
+                    self.interior.push(u8::from(sample.interior));
+                }
+            }
+        }
+    }
+}
+
+/// Everything a render records about one orbit
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Sample {
+    pub count: u32,
+    pub norm: f64,
+    pub smooth: f64,
+    pub distance: f64,
+    pub interior: bool,
+}
+
+impl Sample {
+    /// An orbit that has not escaped within `max_iter` steps
+    pub(crate) fn bounded(max_iter: u32) -> Sample {
+        Sample {
+            count: max_iter,
+            norm: 0.0,
+            smooth: max_iter as f64,
+            distance: 0.0,
+            interior: false,
+        }
+    }
+
+    /// An orbit that escaped to `z` after `n` steps, with derivative `der`
+    ///
+    /// Follows the orbit on for the smooth count and distance estimate if the
+    /// channels ask for them; `der` gains `dc` at each step as in [`step`].
+    pub(crate) fn escaped(
+        n: u32,
+        z: Complex,
+        der: Complex,
+        (c, dc): (Complex, f64),
+        channels: Channels,
+    ) -> Sample {
+        let (mut z, mut der) = (z, der);
+        let mut norm = z.norm_sqr();
+        let mut sample = Sample {
+            count: n,
+            norm,
+            smooth: n as f64,
+            distance: 0.0,
+            interior: false,
+        };
+        if !(channels.smooth || channels.distance) {
+            return sample;
+        }
+        // Both estimates are asymptotic in |z|, so follow the orbit further out
+        let mut m = n;
+        while norm <= SMOOTH_ESCAPE_RADIUS * SMOOTH_ESCAPE_RADIUS && m < n + MAX_EXTRA_STEPS {
+            norm = step(&mut z, &mut der, c, dc, channels.distance);
+            m += 1;
+        }
+        let log_z = 0.5 * norm.ln();
+        // With log₂|z| rather than ln|z|, ν stays close to the count at radius 2
+        sample.smooth = m as f64 + 1.0 - (log_z / std::f64::consts::LN_2).log2();
+        let distance = 2.0 * norm.sqrt() * log_z / der.re.hypot(der.im);
+        // The derivative overflows for points very close to the set
+        sample.distance = if distance.is_finite() { distance } else { 0.0 };
+        sample
+    }
+}
+
+/// One step of z → z² + c, returning the new |z|²
+///
+/// If `track` is set, `der` follows the derivative of z: it gains `dc` at each
+/// step, 1 for the derivative with respect to `c` and 0 for `z₀`.
+pub(crate) fn step(z: &mut Complex, der: &mut Complex, c: Complex, dc: f64, track: bool) -> f64 {
+    let (x, y) = (z.re, z.im);
+    if track {
+        *der = Complex::new(
+            2.0 * (x * der.re - y * der.im) + dc,
+            2.0 * (x * der.im + y * der.re),
+        );
+    }
+    *z = Complex::new(x * x - y * y + c.re, 2.0 * x * y + c.im);
+    z.norm_sqr()
+}
+
+/// Follow z → z² + c from `z` for the channels asked for
+///
+/// The derivative is taken with respect to `c` for the Mandelbrot set, where
+/// `z` starts at 0, and with respect to `z` for Julia sets.
+fn trace(z: Complex, c: Complex, mandelbrot: bool, max_iter: u32, channels: Channels) -> Sample {
+    let mut sample = Sample::bounded(max_iter);
+    if mandelbrot && channels.interior && in_main_components(c) {
+        sample.interior = true;
+        return sample;
+    }
+    let (mut z, dc) = (z, if mandelbrot { 1.0 } else { 0.0 });
+    let mut der = Complex::new(1.0 - dc, 0.0);
+    // Brent's cycle detection: compare with a point saved at doubling intervals
+    let (mut saved, mut window, mut since) = (z, 1, 0);
+    let mut norm = z.norm_sqr();
+    let mut n = 0;
+    while n < max_iter && norm <= ESCAPE_RADIUS * ESCAPE_RADIUS {
+        norm = step(&mut z, &mut der, c, dc, channels.distance);
+        n += 1;
+        if channels.interior {
+            if (z.re - saved.re).abs() + (z.im - saved.im).abs() < PERIOD_TOLERANCE {
+                sample.interior = true;
+                sample.norm = norm;
+                return sample;
+            }
+            since += 1;
+            if since == window {
+                (saved, window, since) = (z, window * 2, 0);
+            }
+        }
+    }
+    // An orbit can escape on the last step, so test the norm rather than n
+    if norm <= ESCAPE_RADIUS * ESCAPE_RADIUS {
+        sample.norm = norm;
+        return sample;
+    }
+    Sample::escaped(n, z, der, (c, dc), channels)
+}
+
+/// Whether `c` lies in the main cardioid or the period-2 bulb of the Mandelbrot set
+fn in_main_components(c: Complex) -> bool {
+    let x = c.re - 0.25;
+    let q = x * x + c.im * c.im;
+    let cardioid = q * (q + x) <= 0.25 * c.im * c.im;
+    let bulb = (c.re + 1.0) * (c.re + 1.0) + c.im * c.im <= 1.0 / 16.0;
+    cardioid || bulb
+}
+
+/// Iterate z → z² + c from `z` until |z| > [`ESCAPE_RADIUS`] or `max_iter` steps
+///
+/// Returns the number of steps taken and the final |z|².
+pub fn escape(z: Complex, c: Complex, max_iter: u32) -> (u32, f64) {
+    let sample = trace(z, c, false, max_iter, Channels::default());
+    (sample.count, sample.norm)
+}
+
+/// Render the Mandelbrot set over `viewport` into `out`
+#[wasm_bindgen(js_name = renderMandelbrot)]
+pub fn render_mandelbrot(
+    viewport: &Viewport,
+    channels: &Channels,
+    out: &mut EscapeGrid,
+) -> Result<(), Diagnostic> {
+    viewport.check()?;
+    let size = (viewport.width, viewport.height);
+    let origin = Complex::new(0.0, 0.0);
+    out.render(
+        FractalKind::Mandelbrot,
+        origin,
+        size,
+        viewport.max_iter,
+        *channels,
+        |x, y| viewport.pixel(x, y),
+    );
+    Ok(())
+}
+
+/// Render the filled Julia set of z → z² + `c` over `viewport` into `out`
+#[wasm_bindgen(js_name = renderJulia)]
+pub fn render_julia(
+    viewport: &Viewport,
+    c: Complex,
+    channels: &Channels,
+    out: &mut EscapeGrid,
+) -> Result<(), Diagnostic> {
+    viewport.check()?;
+    let size = (viewport.width, viewport.height);
+    out.render(
+        FractalKind::Julia,
+        c,
+        size,
+        viewport.max_iter,
+        *channels,
+        |x, y| viewport.pixel(x, y),
+    );
+    Ok(())
+}
+
+/// Points on the Julia set of z → z² + `c`, interleaved `[x0, y0, x1, y1, ...]`
+///
+/// Walks the tree of preimages z → ±√(z − c) depth-first from the repelling
+/// fixed point, which lies on the set. Each pixel of `viewport` is visited at
+/// most `max_hits` times before the branches through it are pruned, so the
+/// points cover the set evenly instead of piling up where inverse iteration
+/// lands most often. Branches that leave the viewport are not followed, so
+/// the viewport should contain the whole set for a complete picture. Stops
+/// after `max_points` points.
+#[wasm_bindgen(js_name = juliaBoundary)]
+pub fn julia_boundary(
+    c: Complex,
+    viewport: &Viewport,
+    max_hits: u32,
+    max_points: u32,
+) -> Result<Vec<f64>, Diagnostic> {
+    viewport.check()?;
+    if max_hits == 0 || !(c.re.is_finite() && c.im.is_finite()) {
+        return Err(Diagnostic::new(
+            ErrorKind::Argument,
+            "The boundary needs a finite c and at least one hit per pixel",
+        ));
+    }
+    let mut hits = vec![0u32; viewport.width as usize * viewport.height as usize];
+    let mut points = Vec::new();
+    // z* = 1/2 + √(1/4 − c), the fixed point with |f′(z*)| = |2z*| >= 1
+    let start = Complex::new(0.25 - c.re, -c.im).sqrt();
+    let mut stack = vec![Complex::new(0.5 + start.re, start.im)];
+    while let Some(z) = stack.pop() {
+        if points.len() >= 2 * max_points as usize {
+            break;
+        }
+        let Some(i) = viewport.index_of(z) else {
+            continue;
+        };
+        if hits[i] >= max_hits {
+            continue;
+        }
+        hits[i] += 1;
+        points.extend([z.re, z.im]);
+        let w = Complex::new(z.re - c.re, z.im - c.im).sqrt();
+        stack.extend([w, Complex::new(-w.re, -w.im)]);
+    }
+    Ok(points)
+}
@@ -0,0 +1,226 @@
+//! Preimages of typed-in functions, for C⁻¹ and preimage trees
+//!
+//! The interval is split at the turning points of `f` into pieces where it is
+//! strictly monotone. Each piece holds at most one preimage of a value, which
+//! Brent's method finds from the bracket given by the piece's ends.
+
+use wasm_bindgen::prelude::*;
+
+use crate::{Diagnostic, ErrorKind, curve::Expression};
+
+/// Iterations before Brent's method gives up
+const MAX_ITERATIONS: u32 = 200;
+
+/// Preimages of a value under `f`, with where `f` can and cannot be inverted
+#[wasm_bindgen(getter_with_clone)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Inversion {
+    /// Every `x` with `f(x) = y` found in the interval, ascending
+    pub preimages: Vec<f64>,
+    /// A bound on the distance from each preimage to the true root
+    pub errors: Vec<f64>,
+    /// Intervals `[start, end, ...]` on which `f` is strictly monotone, so invertible
+    pub monotone: Vec<f64>,
+    /// Intervals `[start, end, ...]` where `f` is undefined or constant, so has no inverse
+    pub non_invertible: Vec<f64>,
+}
+
+#[wasm_bindgen]
+impl Inversion {
+    /// Whether `f` is one-to-one on the whole interval
+    #[wasm_bindgen(getter)]
+    pub fn injective(&self) -> bool {
+        self.monotone.len() == 2 && self.non_invertible.is_empty()
+    }
+}
+
+/// Find every `x` in `[x_min, x_max]` with `f(x) = y`
+///
+/// `f` is sampled at `samples + 1` points to find its turning points and the
+/// regions where it is undefined or flat; a turning point or root closer
+/// together than the sample spacing may be missed. Preimages are located to
+/// within `tolerance`.
+#[wasm_bindgen]
+pub fn invert(
+    f: &Expression,
+    y: f64,
+    x_min: f64,
+    x_max: f64,
+    samples: u32,
+    tolerance: f64,
+) -> Result<Inversion, Diagnostic> {
+    let valid = x_min < x_max && tolerance > 0.0 && y.is_finite();
+    if samples == 0 || !valid {
+        return Err(Diagnostic::new(
+            ErrorKind::Argument,
+            "Inversion needs a finite target, x_min < x_max, at least one sample and a positive tolerance",
+        ));
+    }
+    let mut inversion = Inversion {
+        preimages: Vec::new(),
+        errors: Vec::new(),
+        monotone: Vec::new(),
+        non_invertible: Vec::new(),
+    };
+    for (a, b) in monotone_pieces(f, x_min, x_max, samples, &mut inversion.non_invertible) {
+        inversion.monotone.extend([a, b]);
+        let Some((x, error)) = brent(|x| f.eval(x) - y, a, b, tolerance) else {
+            continue;
+        };
+        // Neighbouring pieces share their turning point, which may be the root
+        let repeated = (inversion.preimages.last()).is_some_and(|&p| (x - p).abs() <= tolerance);
+        if !repeated {
+            inversion.preimages.push(x);
+            inversion.errors.push(error);
+        }
+    }
+    Ok(inversion)
+}
+
+/// How `f` behaves at a sample
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Slope {
+    Rising,
+    Falling,
+    Flat,
+    Undefined,
+}
+
+/// Split `[x_min, x_max]` into strictly monotone pieces, pushing the gaps between them onto `gaps`
+fn monotone_pieces(
+    f: &Expression,
+    x_min: f64,
+    x_max: f64,
+    samples: u32,
+    gaps: &mut Vec<f64>,
+) -> Vec<(f64, f64)> {
+    let xs: Vec<f64> = (0..=samples)
+        .map(|i| x_min + (i as f64 / samples as f64) * (x_max - x_min))
+        .collect();
+    let slopes: Vec<Slope> = (xs.iter())
+        .map(|&x| {
+            let (value, d) = (f.eval(x), f.derivative(x));
+            match d {
+                _ if !value.is_finite() || !d.is_finite() => Slope::Undefined,
+                0.0 => Slope::Flat,
+                _ if d > 0.0 => Slope::Rising,
+                _ => Slope::Falling,
+            }
+        })
+        .collect();
+    // An isolated flat sample, like x³ at 0, does not stop f being monotone
+    let breaks = |i: usize| match slopes[i] {
+        Slope::Undefined => true,
+        Slope::Flat => {
+            (i > 0 && slopes[i - 1] == Slope::Flat) || slopes.get(i + 1) == Some(&Slope::Flat)
+        }
+        _ => false,
+    };
+
+    let mut pieces = Vec::new();
+    // Start of the current piece and the direction it runs in, once known
+    let mut piece: Option<(f64, Slope)> = None;
+    let mut gap_start = None;
+    for (i, (&x, &slope)) in xs.iter().zip(&slopes).enumerate() {
+        if breaks(i) {
+            if let Some((start, _)) = piece.take() {
+                pieces.push((start, xs[i - 1]));
+            }
+            gap_start.get_or_insert(x);
+            continue;
+        }
+        if let Some(start) = gap_start.take() {
+            gaps.extend([start, xs[i - 1]]);
+        }
+        match piece {
+            None => piece = Some((x, slope)),
+            Some((start, Slope::Flat)) => piece = Some((start, slope)),
+            Some((start, direction)) if slope != Slope::Flat && slope != direction => {
+                // f′ changes sign between the samples, at a turning point
+                let turn = brent(|x| f.derivative(x), xs[i - 1], x, f64::EPSILON)
+                    .map_or(xs[i - 1], |(t, _)| t);
+                pieces.push((start, turn));
+                piece = Some((turn, slope));
+            }
+            Some(_) => {}
+        }
+    }
+    if let Some((start, _)) = piece {
+        pieces.push((start, xs[samples as usize]));
+    }
+    if let Some(start) = gap_start {
+        gaps.extend([start, xs[samples as usize]]);
+    }
+    pieces
+}
+
+/// A root of `f` in `[a, b]` by Brent's method, with a bound on its error
+///
+/// `f(a)` and `f(b)` must differ in sign, or one of them be zero. Stops once
+/// the bracket is narrower than `tolerance` (or as narrow as `f64` allows).
+/// Returns `None` without a sign change or if `f` is undefined on the way.
+pub fn brent(f: impl Fn(f64) -> f64, a: f64, b: f64, tolerance: f64) -> Option<(f64, f64)> {
+    let (mut a, mut b) = (a, b);
+    let (mut fa, mut fb) = (f(a), f(b));
+    if fa == 0.0 {
+        return Some((a, 0.0));
+    }
+    if fb == 0.0 {
+        return Some((b, 0.0));
+    }
+    if fa.is_nan() || fb.is_nan() || fa.signum() == fb.signum() {
+        return None;
+    }
+    // The root stays between b, the best estimate, and c
+    let (mut c, mut fc) = (a, fa);
+    let (mut d, mut e) = (b - a, b - a);
+    for _ in 0..MAX_ITERATIONS {
+        if fb.signum() == fc.signum() {
+            (c, fc) = (a, fa);
+            (d, e) = (b - a, b - a);
+        }
+        if fc.abs() < fb.abs() {
+            (a, fa) = (b, fb);
+            (b, fb) = (c, fc);
+            (c, fc) = (a, fa);
+        }
+        let tol = 2.0 * f64::EPSILON * b.abs() + 0.5 * tolerance;
+        let half = 0.5 * (c - b);
+        if half.abs() <= tol || fb == 0.0 {
+            let error = if fb == 0.0 { 0.0 } else { (c - b).abs() };
+            return Some((b, error));
+        }
+        if e.abs() >= tol && fa.abs() > fb.abs() {
+            // Try inverse quadratic interpolation, or the secant step if only two points are known
+            let s = fb / fa;
+            let (mut p, mut q) = if a == c {
+                (2.0 * half * s, 1.0 - s)
+            } else {
+                let (q, r) = (fa / fc, fb / fc);
+                (
+                    s * (2.0 * half * q * (q - r) - (b - a) * (r - 1.0)),
+                    (q - 1.0) * (r - 1.0) * (s - 1.0),
+                )
+            };
+            if p > 0.0 {
+                q = -q;
+            }
+            p = p.abs();
+            // Accept the step only if it stays well inside the bracket and is converging
+            if 2.0 * p < (3.0 * half * q - (tol * q).abs()).min((e * q).abs()) {
+                (e, d) = (d, p / q);
+            } else {
+                (d, e) = (half, half);
+            }
+        } else {
+            (d, e) = (half, half);
+        }
+        (a, fa) = (b, fb);
+        b += if d.abs() > tol { d } else { tol.copysign(half) };
+        fb = f(b);
+        if fb.is_nan() {
+            return None;
+        }
+    }
+    Some((b, (c - b).abs()))
+}
@@ -1,59 +1,133 @@
-use uiua::{Uiua, Value};
+mod args;
+pub mod bifurcation;
+mod buffer;
+pub mod color;
+pub mod conjugacy;
+pub mod curve;
+pub mod deep_zoom;
+mod error;
+pub mod expr;
+pub mod fixed_point;
+pub mod fractal;
+pub mod inverse;
+mod limits;
+pub mod logistic;
+pub mod lyapunov;
+pub mod maps;
+mod modules;
+mod output;
+pub mod render;
+mod session;
+pub mod user_map;
+
 use wasm_bindgen::prelude::*;
 
+pub use args::Arg;
+pub use buffer::OutputBuffer;
+pub use error::{Diagnostic, ErrorKind, SourceSpan};
+pub use limits::{CancelToken, Limits};
+pub use modules::{EMBEDDED_MODULES, ModuleFs};
+pub use output::{Output, ValueKind};
+pub use session::UiuaSession;
+
+#[cfg(target_arch = "wasm32")]
 #[wasm_bindgen]
 extern "C" {
     #[wasm_bindgen(js_namespace = console)]
     fn log(s: &str);
 }
 
+#[cfg(target_arch = "wasm32")]
 macro_rules! console_log {
     ($($t:tt)*) => (log(&format!($($t)*)))
 }
 
+// Native builds (the CLI and tests) have no browser console
+#[cfg(not(target_arch = "wasm32"))]
+macro_rules! console_log {
+    ($($t:tt)*) => {};
+}
+
+/// The outcome of a Uiua run: either the values left on the stack or a diagnostic
+#[wasm_bindgen(getter_with_clone)]
+pub struct AlgoResult {
+    /// Everything left on the stack, top first (empty when `error` is set)
+    pub values: Vec<Output>,
+    pub error: Option<Diagnostic>,
+}
+
 #[wasm_bindgen]
-pub fn run_algo(code: &str) -> Vec<f64> {
-    // 1. Create a safe Uiua instance (no file system access)
-    let mut uiua = Uiua::with_safe_sys();
-
-    // 2. Run the code
-    match uiua.run_str(code) {
-        Ok(_) => {
-            // 4. Pop the result
-            match uiua.pop("result") {
-                Ok(val) => {
-                    // Extract f64 data from the Value, regardless of shape
-                    let nums = extract_nums(&val);
-                    console_log!("Uiua returned {} numbers", nums.len());
-                    nums
-                }
-                Err(e) => {
-                    console_log!("Uiua pop error: {}", e);
-                    vec![]
-                }
-            }
-        }
-        Err(e) => {
-            console_log!("Uiua run error: {}", e);
-            vec![]
-        }
+impl AlgoResult {
+    /// The value on top of the stack
+    #[wasm_bindgen(getter)]
+    pub fn value(&self) -> Option<Output> {
+        self.values.first().cloned()
+    }
+
+    /// The value labelled `name`, e.g. `orbit` for a module that leaves `$orbit` on the stack
+    pub fn named(&self, name: &str) -> Option<Output> {
+        (self.values.iter())
+            .find(|out| out.label.as_deref() == Some(name))
+            .cloned()
+    }
+
+    /// Labels of every labelled value on the stack, top first
+    pub fn names(&self) -> Vec<String> {
+        self.values
+            .iter()
+            .filter_map(|out| out.label.clone())
+            .collect()
     }
 }
 
-/// Extract all numeric values from a Uiua Value as a flat Vec<f64>
-fn extract_nums(val: &Value) -> Vec<f64> {
-    match val {
-        Value::Num(arr) => {
-            // Use elements() iterator to access the data
-            arr.elements().copied().collect()
-        }
-        Value::Byte(arr) => {
-            // Convert bytes to f64
-            arr.elements().map(|&b| b as f64).collect()
-        }
-        _ => {
-            console_log!("Unsupported value type for numeric extraction");
-            vec![]
+impl From<Result<Vec<Output>, Diagnostic>> for AlgoResult {
+    fn from(res: Result<Vec<Output>, Diagnostic>) -> Self {
+        match res {
+            Ok(values) => AlgoResult {
+                values,
+                error: None,
+            },
+            Err(error) => AlgoResult {
+                values: Vec::new(),
+                error: Some(error),
+            },
         }
     }
 }
+
+/// Run `code` and return the values left on the stack
+///
+/// Imports such as `~ "prelude.ua" ~ Domain` resolve against the embedded
+/// `uiua-modules/`, so error spans carry the module's own line numbers.
+#[wasm_bindgen]
+pub fn run_algo(code: &str, limits: Option<Limits>) -> AlgoResult {
+    run(code, limits.unwrap_or_default()).into()
+}
+
+/// Call the binding `name` defined by `code`, with `args` pushed onto the stack
+///
+/// Arguments are given in call order, so `call_algo(m, "CobwebPath", [steps, r, x0])`
+/// behaves like the Uiua source `CobwebPath steps r x0`.
+#[wasm_bindgen]
+pub fn call_algo(code: &str, name: &str, args: Vec<Arg>, limits: Option<Limits>) -> AlgoResult {
+    call(code, name, args, limits.unwrap_or_default()).into()
+}
+
+/// Run `code` natively, as [`run_algo`] does in the browser
+pub fn run(code: &str, limits: Limits) -> Result<Vec<Output>, Diagnostic> {
+    let values = UiuaSession::load(code, limits)?.take_results()?;
+    console_log!("Uiua returned {} values", values.len());
+    Ok(values)
+}
+
+/// Call `name` natively, as [`call_algo`] does in the browser
+pub fn call(
+    code: &str,
+    name: &str,
+    args: Vec<Arg>,
+    limits: Limits,
+) -> Result<Vec<Output>, Diagnostic> {
+    let values = UiuaSession::load(code, limits)?.call_impl(name, args)?;
+    console_log!("Uiua returned {} values", values.len());
+    Ok(values)
+}
@@ -0,0 +1,186 @@
+use std::sync::{
+    Arc, Mutex,
+    atomic::{AtomicBool, AtomicU8, AtomicU64, Ordering},
+};
+
+use uiua::{UiuaError, UiuaErrorKind};
+use wasm_bindgen::prelude::*;
+
+use crate::{Diagnostic, ErrorKind};
+
+/// How many instructions run between clock and cancellation checks
+const CHECK_INTERVAL: u64 = 256;
+
+/// Limits applied to every run or call
+#[wasm_bindgen]
+#[derive(Debug, Clone, Default)]
+pub struct Limits {
+    /// Wall-clock limit per run, in milliseconds
+    pub time_limit_ms: Option<f64>,
+    /// Maximum number of interpreter instructions per run
+    pub instruction_budget: Option<u32>,
+    cancel: Option<CancelToken>,
+}
+
+#[wasm_bindgen]
+impl Limits {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Limits {
+        Limits::default()
+    }
+
+    /// Stop runs when `token` is cancelled
+    pub fn set_cancel(&mut self, token: &CancelToken) {
+        self.cancel = Some(token.clone());
+    }
+}
+
+/// A flag checked by the interpreter while a run is in progress
+///
+/// A synchronous run blocks the thread it is on, so in the browser the run
+/// should happen in a worker and the token should wrap an `Int32Array` over a
+/// `SharedArrayBuffer` that the main thread sets to non-zero to cancel.
+#[wasm_bindgen]
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken {
+    flag: Arc<AtomicBool>,
+    #[cfg(target_arch = "wasm32")]
+    shared: Option<js_sys::Int32Array>,
+}
+
+#[wasm_bindgen]
+impl CancelToken {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> CancelToken {
+        CancelToken::default()
+    }
+
+    /// A token that also reads element 0 of a shared `Int32Array`
+    #[cfg(target_arch = "wasm32")]
+    pub fn shared(buffer: js_sys::Int32Array) -> CancelToken {
+        CancelToken {
+            shared: Some(buffer),
+            ..CancelToken::default()
+        }
+    }
+
+    pub fn cancel(&self) {
+        self.flag.store(true, Ordering::Relaxed);
+    }
+
+    /// Clear the flag so the token can be reused for another run
+    pub fn reset(&self) {
+        self.flag.store(false, Ordering::Relaxed);
+        #[cfg(target_arch = "wasm32")]
+        if let Some(shared) = &self.shared {
+            _ = js_sys::Atomics::store(shared, 0, 0);
+        }
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        #[cfg(target_arch = "wasm32")]
+        if let Some(shared) = &self.shared
+            && js_sys::Atomics::load(shared, 0).is_ok_and(|v| v != 0)
+        {
+            return true;
+        }
+        self.flag.load(Ordering::Relaxed)
+    }
+}
+
+// Why a watchdog stopped a run, as stored in `WatchdogState::trip`
+const TRIP_NONE: u8 = 0;
+const TRIP_TIME: u8 = 1;
+const TRIP_BUDGET: u8 = 2;
+const TRIP_CANCEL: u8 = 3;
+
+/// Enforces [`Limits`] through the interpreter's interrupt hook
+///
+/// Uiua checks the hook after every instruction, so one watchdog is shared by
+/// every runtime a session creates and re-armed at the start of each run.
+#[derive(Clone, Default)]
+pub(crate) struct Watchdog(Arc<WatchdogState>);
+
+#[derive(Default)]
+struct WatchdogState {
+    limits: Mutex<Limits>,
+    instructions: AtomicU64,
+    budget: AtomicU64,
+    deadline: AtomicU64,
+    trip: AtomicU8,
+}
+
+impl Watchdog {
+    pub fn set_limits(&self, limits: Limits) {
+        *self.0.limits.lock().unwrap() = limits;
+    }
+
+    /// Reset counters and the deadline before a run
+    pub fn arm(&self) {
+        let limits = self.0.limits.lock().unwrap();
+        let deadline = (limits.time_limit_ms)
+            .map(|ms| uiua::now() + ms / 1000.0)
+            .unwrap_or(f64::INFINITY);
+        let budget = (limits.instruction_budget).map_or(u64::MAX, u64::from);
+        self.0.deadline.store(deadline.to_bits(), Ordering::Relaxed);
+        self.0.budget.store(budget, Ordering::Relaxed);
+        self.0.instructions.store(0, Ordering::Relaxed);
+        self.0.trip.store(TRIP_NONE, Ordering::Relaxed);
+    }
+
+    /// Build the interrupt hook, which returns `true` once a limit is hit
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn hook(&self) -> impl Fn() -> bool + Send + Sync + 'static {
+        let this = self.clone();
+        move || this.check()
+    }
+
+    /// Build the interrupt hook, which returns `true` once a limit is hit
+    #[cfg(target_arch = "wasm32")]
+    pub fn hook(&self) -> impl Fn() -> bool + 'static {
+        let this = self.clone();
+        move || this.check()
+    }
+
+    fn check(&self) -> bool {
+        let state = &self.0;
+        if state.trip.load(Ordering::Relaxed) != TRIP_NONE {
+            return true;
+        }
+        let count = state.instructions.fetch_add(1, Ordering::Relaxed) + 1;
+        let trip = if count > state.budget.load(Ordering::Relaxed) {
+            TRIP_BUDGET
+        } else if !count.is_multiple_of(CHECK_INTERVAL) {
+            return false;
+        } else if uiua::now() > f64::from_bits(state.deadline.load(Ordering::Relaxed)) {
+            TRIP_TIME
+        } else if (state.limits.lock().unwrap().cancel.as_ref()).is_some_and(|c| c.is_cancelled()) {
+            TRIP_CANCEL
+        } else {
+            return false;
+        };
+        state.trip.store(trip, Ordering::Relaxed);
+        true
+    }
+
+    /// Convert an error raised during `stage`, accounting for limits this watchdog enforced
+    pub fn diagnose(&self, stage: ErrorKind, err: &UiuaError) -> Diagnostic {
+        let trip = self.0.trip.load(Ordering::Relaxed);
+        if !matches!(&*err.kind, UiuaErrorKind::Interrupted) {
+            return Diagnostic::from_uiua(stage, err);
+        }
+        let limits = self.0.limits.lock().unwrap();
+        if trip == TRIP_TIME {
+            let ms = limits.time_limit_ms.unwrap_or_default();
+            Diagnostic::new(ErrorKind::Timeout, format!("Time limit of {ms}ms exceeded"))
+        } else if trip == TRIP_BUDGET {
+            let budget = limits.instruction_budget.unwrap_or_default();
+            Diagnostic::new(
+                ErrorKind::Timeout,
+                format!("Instruction budget of {budget} exceeded"),
+            )
+        } else {
+            Diagnostic::new(ErrorKind::Cancelled, "Run was cancelled")
+        }
+    }
+}
@@ -0,0 +1,91 @@
+//! Native versions of the kernels in `uiua-modules/logistic.ua` and `cobweb.ua`
+//!
+//! The Uiua modules are the readable reference; these are the fast path. Each
+//! kernel performs the same floating-point operations in the same order, so
+//! results match the Uiua versions exactly (see `tests/logistic_kernels.rs`).
+
+use wasm_bindgen::prelude::*;
+
+use crate::{OutputBuffer, ValueKind};
+
+/// The logistic map f(x) = r·x·(1 − x)
+pub fn logistic(r: f64, x: f64) -> f64 {
+    r * x * (1.0 - x)
+}
+
+/// `n + 1` evenly spaced values from `min` to `max`, like `Domain` in `prelude.ua`
+///
+/// With `n` = 0 this is just `min`.
+fn domain(n: u32, min: f64, max: f64) -> impl Iterator<Item = f64> {
+    let step = (max - min) / n.max(1) as f64;
+    (0..=n).map(move |i| i as f64 * step + min)
+}
+
+/// `[x0, x1, ..., x_{steps-1}]`, like `Orbit steps r x0`
+#[wasm_bindgen(js_name = logisticOrbit)]
+pub fn orbit(steps: u32, r: f64, x0: f64) -> Vec<f64> {
+    let mut x = x0;
+    (0..steps)
+        .map(|_| {
+            let prev = x;
+            x = logistic(r, x);
+            prev
+        })
+        .collect()
+}
+
+/// Interleaved cobweb path `(x0, 0) → (x0, x1) → (x1, x1) → ...`, like `CobwebPath steps r x0`
+#[wasm_bindgen(js_name = logisticCobweb)]
+pub fn cobweb_path(steps: u32, r: f64, x0: f64) -> Vec<f64> {
+    let orbit = orbit(steps, r, x0);
+    let Some(&first) = orbit.first() else {
+        return Vec::new();
+    };
+    let mut path = Vec::with_capacity(4 * orbit.len() - 2);
+    path.extend([first, 0.0]);
+    for pair in orbit.windows(2) {
+        path.extend([pair[0], pair[1], pair[1], pair[1]]);
+    }
+    path
+}
+
+/// Interleaved `[x0, y0, x1, y1, ...]` of the parabola over `n + 1` points from `min` to `max`
+///
+/// `LogisticCurve r` is `logisticCurve(r, 100, -0.1, 1.1)`.
+#[wasm_bindgen(js_name = logisticCurve)]
+pub fn curve(r: f64, n: u32, min: f64, max: f64) -> Vec<f64> {
+    domain(n, min, max)
+        .flat_map(|x| [x, logistic(r, x)])
+        .collect()
+}
+
+/// Bifurcation diagram as interleaved `[r, x]` points, like `Bifurcation n rmin rmax transient keep x0`
+///
+/// For each of the `n + 1` values of r from `r_min` to `r_max`, iterates from
+/// `x0`, discards `transient` iterates and keeps the next `keep`. Points are
+/// written into `out` so repeated sweeps reuse its memory.
+#[wasm_bindgen(js_name = logisticBifurcation)]
+#[allow(clippy::too_many_arguments)]
+pub fn bifurcation(
+    out: &mut OutputBuffer,
+    n: u32,
+    r_min: f64,
+    r_max: f64,
+    transient: u32,
+    keep: u32,
+    x0: f64,
+) {
+    let points = domain(n, r_min, r_max).flat_map(|r| {
+        let mut x = x0;
+        for _ in 0..transient {
+            x = logistic(r, x);
+        }
+        (0..keep).flat_map(move |_| {
+            let prev = x;
+            x = logistic(r, x);
+            [r, prev]
+        })
+    });
+    let len = 2 * (n as usize + 1) * keep as usize;
+    out.write(ValueKind::Num, &[len], points);
+}
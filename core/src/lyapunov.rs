@@ -0,0 +1,235 @@
+//! Lyapunov exponents from products of map Jacobians
+//!
+//! Tangent vectors are pushed through the Jacobian each step and re-orthonormalised
+//! with Gram–Schmidt (a QR step), so every exponent is the average log stretch
+//! along one direction. 1D maps have a single exponent, 2D maps a full spectrum.
+
+use wasm_bindgen::prelude::*;
+
+use crate::{
+    Diagnostic, ErrorKind, OutputBuffer,
+    bifurcation::{Columns, SweepOptions},
+    maps::{self, Jacobian, JacobianFn, MapDef, State, escaped},
+};
+
+/// The averaging window is split into this many blocks to estimate convergence
+const BLOCKS: usize = 10;
+
+/// Lyapunov exponents with their convergence estimates, largest exponent first
+#[derive(Debug, Clone, PartialEq)]
+pub struct Spectrum {
+    pub exponents: Vec<f64>,
+    /// Standard error of each exponent, from the spread of its block averages
+    ///
+    /// NaN when there are too few steps to tell.
+    pub errors: Vec<f64>,
+}
+
+/// Running log-stretch sums for each direction, split into blocks
+struct Accumulator {
+    /// `sums[k][b]` and `counts[k][b]` for exponent `k` over block `b`
+    sums: Vec<[f64; BLOCKS]>,
+    counts: Vec<[u32; BLOCKS]>,
+}
+
+impl Accumulator {
+    fn new(dimension: usize) -> Self {
+        Accumulator {
+            sums: vec![[0.0; BLOCKS]; dimension],
+            counts: vec![[0; BLOCKS]; dimension],
+        }
+    }
+
+    /// Add `log |stretch|`, skipping zero stretches (e.g. at a superstable point)
+    /// as the JS sketches do
+    fn add(&mut self, k: usize, block: usize, stretch: f64) {
+        if stretch != 0.0 {
+            self.sums[k][block] += stretch.abs().ln();
+            self.counts[k][block] += 1;
+        }
+    }
+
+    fn finish(self) -> Option<Spectrum> {
+        let mut spectrum = Spectrum {
+            exponents: Vec::new(),
+            errors: Vec::new(),
+        };
+        for (sums, counts) in self.sums.iter().zip(&self.counts) {
+            let total: u32 = counts.iter().sum();
+            if total == 0 {
+                return None;
+            }
+            spectrum
+                .exponents
+                .push(sums.iter().sum::<f64>() / total as f64);
+            spectrum.errors.push(block_error(sums, counts));
+        }
+        Some(spectrum)
+    }
+}
+
+/// Standard error of the mean across the non-empty blocks
+fn block_error(sums: &[f64], counts: &[u32]) -> f64 {
+    let means: Vec<f64> = (sums.iter().zip(counts))
+        .filter(|&(_, &c)| c > 0)
+        .map(|(s, &c)| s / c as f64)
+        .collect();
+    let n = means.len() as f64;
+    if means.len() < 2 {
+        return f64::NAN;
+    }
+    let mean = means.iter().sum::<f64>() / n;
+    let variance = means.iter().map(|m| (m - mean).powi(2)).sum::<f64>() / (n - 1.0);
+    (variance / n).sqrt()
+}
+
+fn jacobian_of(map: &MapDef) -> Result<JacobianFn, Diagnostic> {
+    map.jacobian.ok_or_else(|| {
+        Diagnostic::new(
+            ErrorKind::Argument,
+            format!(
+                "{} has no Jacobian, so its Lyapunov exponents are unknown",
+                map.name
+            ),
+        )
+    })
+}
+
+fn apply(j: &Jacobian, v: [f64; 2]) -> [f64; 2] {
+    [
+        j[0][0] * v[0] + j[0][1] * v[1],
+        j[1][0] * v[0] + j[1][1] * v[1],
+    ]
+}
+
+fn norm(v: [f64; 2]) -> f64 {
+    v[0].hypot(v[1])
+}
+
+/// Lyapunov spectrum of `map` along the orbit from `ic`
+///
+/// Discards `transient` steps, then averages over the next `steps`. Returns
+/// `None` if the orbit escapes or every step has zero stretch.
+pub fn spectrum(
+    map: &MapDef,
+    params: &[f64],
+    ic: State,
+    transient: u32,
+    steps: u32,
+) -> Result<Option<Spectrum>, Diagnostic> {
+    let jacobian = jacobian_of(map)?;
+    Ok(spectrum_of(
+        map.dimension,
+        |state| (map.step)(state, params),
+        |state| jacobian(state, params),
+        ic,
+        transient,
+        steps,
+    ))
+}
+
+/// [`spectrum`] for any map given as step and Jacobian closures
+pub(crate) fn spectrum_of(
+    dimension: u32,
+    step: impl Fn(State) -> State,
+    jacobian: impl Fn(State) -> Jacobian,
+    ic: State,
+    transient: u32,
+    steps: u32,
+) -> Option<Spectrum> {
+    let mut state = ic;
+    for _ in 0..transient {
+        state = step(state);
+        if escaped(state) {
+            return None;
+        }
+    }
+    let mut acc = Accumulator::new(dimension as usize);
+    // Orthonormal tangent basis, re-orthonormalised after every step
+    let mut basis = [[1.0, 0.0], [0.0, 1.0]];
+    for i in 0..steps {
+        let block = i as usize * BLOCKS / steps as usize;
+        let j = jacobian(state);
+        if dimension == 1 {
+            acc.add(0, block, j[0][0]);
+        } else {
+            let mut w1 = apply(&j, basis[0]);
+            let mut w2 = apply(&j, basis[1]);
+            let n1 = norm(w1);
+            acc.add(0, block, n1);
+            if n1 > 0.0 {
+                w1 = [w1[0] / n1, w1[1] / n1];
+                basis[0] = w1;
+            }
+            let dot = w2[0] * basis[0][0] + w2[1] * basis[0][1];
+            w2 = [w2[0] - dot * basis[0][0], w2[1] - dot * basis[0][1]];
+            let n2 = norm(w2);
+            acc.add(1, block, n2);
+            basis[1] = match n2 > 0.0 {
+                true => [w2[0] / n2, w2[1] / n2],
+                false => [-basis[0][1], basis[0][0]],
+            };
+        }
+        state = step(state);
+        if escaped(state) {
+            return None;
+        }
+    }
+    acc.finish()
+}
+
+/// Lyapunov spectrum of `map_id` from `ic`, as `[λ1, err1, λ2, err2]` (one pair for 1D maps)
+///
+/// Empty if the orbit escapes.
+#[wasm_bindgen(js_name = lyapunovSpectrum)]
+pub fn lyapunov_spectrum(
+    map_id: &str,
+    params: Vec<f64>,
+    ic: Vec<f64>,
+    transient: u32,
+    steps: u32,
+) -> Result<Vec<f64>, Diagnostic> {
+    let map = maps::find(map_id)?;
+    map.check_params(&params)?;
+    let ic = maps::state(&ic)?;
+    let spectrum = spectrum(map, &params, ic, transient, steps)?;
+    Ok(spectrum.map(|s| interleave(&s)).unwrap_or_default())
+}
+
+/// `[λ1, err1, λ2, err2, ...]`
+pub(crate) fn interleave(s: &Spectrum) -> Vec<f64> {
+    (s.exponents.iter().zip(&s.errors))
+        .flat_map(|(&l, &e)| [l, e])
+        .collect()
+}
+
+/// Lyapunov exponents across a sweep of `sweep_param`, as rows of `[param, λ1, err1, λ2, err2]`
+///
+/// Uses the sweep's `transient`, initial-condition policy and seed, and averages
+/// over `samples` steps; `dedup_tolerance` does not apply. 1D maps give rows of
+/// `[param, λ, err]`. Columns whose orbit escapes are left out.
+#[wasm_bindgen(js_name = lyapunovSweep)]
+pub fn lyapunov_sweep(
+    map_id: &str,
+    params: Vec<f64>,
+    sweep_param: &str,
+    options: &SweepOptions,
+    out: &mut OutputBuffer,
+) -> Result<(), Diagnostic> {
+    let mut columns = Columns::new(map_id, params, sweep_param, options)?;
+    jacobian_of(columns.map)?;
+    let width = 1 + 2 * columns.map.dimension as usize;
+    out.write_rows(width, |rows| {
+        while let Some((value, ic)) = columns.next_column() {
+            let map = columns.map;
+            // The Jacobian was checked above, so this cannot fail
+            if let Ok(Some(s)) =
+                spectrum(map, &columns.params, ic, options.transient, options.samples)
+            {
+                rows.push(value);
+                rows.extend(interleave(&s));
+            }
+        }
+    });
+    Ok(())
+}
@@ -0,0 +1,239 @@
+//! Registry of the iterated maps the app can plot
+//!
+//! Each map is described once here, with its parameters, plot bounds, step
+//! function and Jacobian, so the iteration, bifurcation and Lyapunov views all
+//! share one definition. 1D maps use `x` only and keep `y` at 0.
+
+use wasm_bindgen::prelude::*;
+
+use crate::{Diagnostic, ErrorKind, logistic::logistic};
+
+/// A point in the state space, `[x, y]`
+pub type State = [f64; 2];
+
+/// Row-major 2×2 Jacobian, `[[∂x'/∂x, ∂x'/∂y], [∂y'/∂x, ∂y'/∂y]]`
+pub type Jacobian = [[f64; 2]; 2];
+
+/// One step of a map, given the state and the parameters in declared order
+pub type StepFn = fn(State, &[f64]) -> State;
+
+/// The Jacobian of a map at a state
+pub type JacobianFn = fn(State, &[f64]) -> Jacobian;
+
+/// Iterates beyond this magnitude are treated as escaping to infinity
+pub const DIVERGENCE: f64 = 1e10;
+
+/// A map parameter with its default and the range the UI should offer
+#[derive(Debug, Clone, Copy)]
+pub struct Param {
+    pub name: &'static str,
+    pub default: f64,
+    pub min: f64,
+    pub max: f64,
+}
+
+/// A map and everything needed to iterate and plot it
+#[derive(Debug, Clone, Copy)]
+pub struct MapDef {
+    pub id: &'static str,
+    pub name: &'static str,
+    /// 1 for maps of `x` alone, 2 for maps of `(x, y)`
+    pub dimension: u32,
+    /// Parameters in the order `step` and `jacobian` receive them
+    pub params: &'static [Param],
+    /// Suggested plot bounds `[x_min, x_max, y_min, y_max]` for the iteration view
+    pub bounds: [f64; 4],
+    pub step: StepFn,
+    pub jacobian: Option<JacobianFn>,
+}
+
+/// Every registered map
+pub static MAPS: &[MapDef] = &[HENON, LOGISTIC];
+
+pub const HENON: MapDef = MapDef {
+    id: "henon",
+    name: "Hénon",
+    dimension: 2,
+    params: &[
+        Param {
+            name: "a",
+            default: 1.4,
+            min: -1.0,
+            max: 3.0,
+        },
+        Param {
+            name: "b",
+            default: -0.4,
+            min: -1.0,
+            max: 1.0,
+        },
+    ],
+    bounds: [-2.5, 2.5, -2.5, 2.5],
+    step: |[x, y], p| [p[0] - x * x + p[1] * y, x],
+    jacobian: Some(|[x, _], p| [[-2.0 * x, p[1]], [1.0, 0.0]]),
+};
+
+pub const LOGISTIC: MapDef = MapDef {
+    id: "logistic",
+    name: "Logistic",
+    dimension: 1,
+    params: &[Param {
+        name: "a",
+        default: 3.7,
+        min: 0.0,
+        max: 4.0,
+    }],
+    bounds: [0.0, 1.0, 0.0, 1.0],
+    step: |[x, _], p| [logistic(p[0], x), 0.0],
+    jacobian: Some(|[x, _], p| [[p[0] * (1.0 - 2.0 * x), 0.0], [0.0, 0.0]]),
+};
+
+/// The registered map with the given id
+pub fn find(id: &str) -> Result<&'static MapDef, Diagnostic> {
+    (MAPS.iter())
+        .find(|map| map.id == id)
+        .ok_or_else(|| Diagnostic::new(ErrorKind::Argument, format!("Unknown map `{id}`")))
+}
+
+impl MapDef {
+    /// Check that `params` has one value per declared parameter
+    pub fn check_params(&self, params: &[f64]) -> Result<(), Diagnostic> {
+        if params.len() == self.params.len() {
+            return Ok(());
+        }
+        let names: Vec<&str> = self.params.iter().map(|p| p.name).collect();
+        Err(Diagnostic::new(
+            ErrorKind::Argument,
+            format!(
+                "{} takes {} parameters ({}) but {} were given",
+                self.name,
+                names.len(),
+                names.join(", "),
+                params.len()
+            ),
+        ))
+    }
+
+    pub fn default_params(&self) -> Vec<f64> {
+        self.params.iter().map(|p| p.default).collect()
+    }
+
+    /// The last `lag + 1` states of an orbit of `n` steps from `ic`, interleaved `[x, y, ...]`
+    ///
+    /// Stops early, after the first state beyond [`DIVERGENCE`], if the orbit escapes.
+    pub fn iterate(&self, params: &[f64], ic: State, n: u32, lag: u32) -> Vec<f64> {
+        iterate_with(|state| (self.step)(state, params), ic, n, lag)
+    }
+}
+
+/// [`MapDef::iterate`] for any map given as a step closure
+pub(crate) fn iterate_with(step: impl Fn(State) -> State, ic: State, n: u32, lag: u32) -> Vec<f64> {
+    let first = n.saturating_sub(lag);
+    let mut out = Vec::with_capacity(2 * (n - first + 1) as usize);
+    let mut state = ic;
+    for i in 0..=n {
+        if i >= first {
+            out.extend(state);
+        }
+        if escaped(state) {
+            break;
+        }
+        if i < n {
+            state = step(state);
+        }
+    }
+    out
+}
+
+/// Whether `state` is beyond [`DIVERGENCE`] or no longer a number
+pub fn escaped(state: State) -> bool {
+    state.iter().any(|v| v.is_nan() || v.abs() > DIVERGENCE)
+}
+
+/// A map parameter as seen from JavaScript
+#[wasm_bindgen(getter_with_clone)]
+#[derive(Debug, Clone)]
+pub struct ParamInfo {
+    pub name: String,
+    pub default: f64,
+    pub min: f64,
+    pub max: f64,
+}
+
+/// A registered map's metadata as seen from JavaScript
+#[wasm_bindgen(getter_with_clone)]
+#[derive(Debug, Clone)]
+pub struct MapInfo {
+    pub id: String,
+    pub name: String,
+    pub dimension: u32,
+    pub params: Vec<ParamInfo>,
+    pub x_min: f64,
+    pub x_max: f64,
+    pub y_min: f64,
+    pub y_max: f64,
+    pub has_jacobian: bool,
+}
+
+impl From<&MapDef> for MapInfo {
+    fn from(map: &MapDef) -> Self {
+        let [x_min, x_max, y_min, y_max] = map.bounds;
+        MapInfo {
+            id: map.id.into(),
+            name: map.name.into(),
+            dimension: map.dimension,
+            params: (map.params.iter())
+                .map(|p| ParamInfo {
+                    name: p.name.into(),
+                    default: p.default,
+                    min: p.min,
+                    max: p.max,
+                })
+                .collect(),
+            x_min,
+            x_max,
+            y_min,
+            y_max,
+            has_jacobian: map.jacobian.is_some(),
+        }
+    }
+}
+
+/// Metadata for every registered map
+#[wasm_bindgen(js_name = listMaps)]
+pub fn list_maps() -> Vec<MapInfo> {
+    MAPS.iter().map(MapInfo::from).collect()
+}
+
+/// Iterate the map `map_id` from `ic` = `[x, y]` and return the last `lag + 1` of `n + 1` states
+///
+/// `params` are given in the order listed by `listMaps`. The result is
+/// interleaved `[x, y, ...]`; it is shorter than requested if the orbit escapes.
+#[wasm_bindgen(js_name = iterateMap)]
+pub fn iterate(
+    map_id: &str,
+    params: Vec<f64>,
+    ic: Vec<f64>,
+    n: u32,
+    lag: u32,
+) -> Result<Vec<f64>, Diagnostic> {
+    let map = find(map_id)?;
+    map.check_params(&params)?;
+    let ic = state(&ic)?;
+    Ok(map.iterate(&params, ic, n, lag))
+}
+
+/// A state from a JS array of one or two numbers
+pub(crate) fn state(values: &[f64]) -> Result<State, Diagnostic> {
+    match *values {
+        [x] => Ok([x, 0.0]),
+        [x, y] => Ok([x, y]),
+        _ => Err(Diagnostic::new(
+            ErrorKind::Argument,
+            format!(
+                "A state needs 1 or 2 values but {} were given",
+                values.len()
+            ),
+        )),
+    }
+}
@@ -0,0 +1,104 @@
+use std::{
+    any::Any,
+    collections::HashMap,
+    path::{Component, Path, PathBuf},
+    sync::Arc,
+};
+
+use uiua::SysBackend;
+
+/// The path the module being run is compiled under
+///
+/// Imports inside it resolve relative to the module root, and error spans in
+/// it are reported without a file name.
+pub(crate) const MAIN_PATH: &str = "main.ua";
+
+/// The `.ua` files in `uiua-modules/`, embedded at compile time
+pub const EMBEDDED_MODULES: &[(&str, &str)] = &[
+    ("prelude.ua", include_str!("../../uiua-modules/prelude.ua")),
+    (
+        "identity.ua",
+        include_str!("../../uiua-modules/identity.ua"),
+    ),
+    (
+        "logistic.ua",
+        include_str!("../../uiua-modules/logistic.ua"),
+    ),
+    ("cobweb.ua", include_str!("../../uiua-modules/cobweb.ua")),
+];
+
+/// An in-memory file system that resolves `~ "prelude.ua"` style imports
+///
+/// Starts with [`EMBEDDED_MODULES`]; files added later shadow embedded ones,
+/// so hot-edited sources can replace what the engine was built with.
+#[derive(Debug, Clone)]
+pub struct ModuleFs {
+    files: Arc<HashMap<PathBuf, String>>,
+}
+
+impl Default for ModuleFs {
+    fn default() -> Self {
+        let files = (EMBEDDED_MODULES.iter())
+            .map(|&(path, src)| (PathBuf::from(path), src.to_string()))
+            .collect();
+        ModuleFs {
+            files: Arc::new(files),
+        }
+    }
+}
+
+impl ModuleFs {
+    /// Add or replace the file at `path`
+    pub fn insert(&mut self, path: &str, source: impl Into<String>) {
+        Arc::make_mut(&mut self.files).insert(normalize(Path::new(path)), source.into());
+    }
+
+    pub fn get(&self, path: &str) -> Option<&str> {
+        self.files
+            .get(&normalize(Path::new(path)))
+            .map(String::as_str)
+    }
+}
+
+/// Strip `.` components and resolve `..` so `./a/../prelude.ua` finds `prelude.ua`
+fn normalize(path: &Path) -> PathBuf {
+    let mut out = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                out.pop();
+            }
+            other => out.push(other),
+        }
+    }
+    out
+}
+
+impl SysBackend for ModuleFs {
+    fn any(&self) -> &dyn Any {
+        self
+    }
+    fn any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+    fn file_exists(&self, path: &str) -> bool {
+        self.get(path).is_some()
+    }
+    fn is_file(&self, path: &str) -> Result<bool, String> {
+        Ok(self.get(path).is_some())
+    }
+    fn file_read_all(&self, path: &Path) -> Result<Vec<u8>, String> {
+        (self.files.get(&normalize(path)))
+            .map(|src| src.as_bytes().to_vec())
+            .ok_or_else(|| format!("Module `{}` not found", path.display()))
+    }
+    // The compiler caches compiled imports on disk; there is no disk, so
+    // accept and drop those writes rather than warn on every import
+    fn file_write_all(&self, _path: &Path, _contents: &[u8]) -> Result<(), String> {
+        Ok(())
+    }
+    fn make_dir(&self, _path: &Path) -> Result<(), String> {
+        Ok(())
+    }
+}
@@ -0,0 +1,90 @@
+use uiua::Value;
+use wasm_bindgen::prelude::*;
+
+/// Element type of a Uiua array
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValueKind {
+    Num,
+    Byte,
+    Complex,
+    Char,
+    Box,
+}
+
+/// A Uiua array converted for JavaScript, keeping its shape
+///
+/// `data` is row-major, so a 2×N matrix arrives as N x values followed by
+/// N y values and an H×W grid as H rows of W. How the elements are stored
+/// depends on `kind`:
+/// - `Num`/`Byte`: one entry in `data` per element
+/// - `Complex`: interleaved `[re0, im0, re1, im1, ...]` in `data`
+/// - `Char`: the characters in `text`, `data` is empty
+/// - `Box`: one nested output per element in `items`, `data` is empty
+#[wasm_bindgen(getter_with_clone)]
+#[derive(Debug, Clone)]
+pub struct Output {
+    pub kind: ValueKind,
+    pub shape: Vec<usize>,
+    pub data: Vec<f64>,
+    pub text: Option<String>,
+    pub items: Vec<Output>,
+    /// The array's label, e.g. `orbit` for a value labelled `$orbit`
+    pub label: Option<String>,
+}
+
+#[wasm_bindgen]
+impl Output {
+    /// Real parts of a complex array as a separate plane
+    pub fn real(&self) -> Vec<f64> {
+        self.plane(0)
+    }
+
+    /// Imaginary parts of a complex array as a separate plane
+    pub fn imag(&self) -> Vec<f64> {
+        match self.kind {
+            ValueKind::Complex => self.plane(1),
+            _ => vec![0.0; self.data.len()],
+        }
+    }
+}
+
+impl Output {
+    pub fn from_value(val: &Value) -> Self {
+        let mut out = Output {
+            kind: ValueKind::Num,
+            shape: val.shape.iter().copied().collect(),
+            data: Vec::new(),
+            text: None,
+            items: Vec::new(),
+            label: val.meta().label.as_ref().map(|label| label.to_string()),
+        };
+        match val {
+            Value::Num(arr) => out.data = arr.elements().copied().collect(),
+            Value::Byte(arr) => {
+                out.kind = ValueKind::Byte;
+                out.data = arr.elements().map(|&b| b as f64).collect();
+            }
+            Value::Complex(arr) => {
+                out.kind = ValueKind::Complex;
+                out.data = arr.elements().flat_map(|c| [c.re, c.im]).collect();
+            }
+            Value::Char(arr) => {
+                out.kind = ValueKind::Char;
+                out.text = Some(arr.elements().collect());
+            }
+            Value::Box(arr) => {
+                out.kind = ValueKind::Box;
+                out.items = arr.elements().map(|b| Output::from_value(&b.0)).collect();
+            }
+        }
+        out
+    }
+
+    fn plane(&self, offset: usize) -> Vec<f64> {
+        match self.kind {
+            ValueKind::Complex => self.data.iter().skip(offset).step_by(2).copied().collect(),
+            _ => self.data.clone(),
+        }
+    }
+}
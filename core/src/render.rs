@@ -0,0 +1,198 @@
+//! Tiled, progressively refined fractal renders
+//!
+//! A [`RenderRequest`] mirrors the `RenderRequest` in `NewProject.md`, plus a
+//! refinement level and an id. The frontend renders a whole view coarsely
+//! first, then tile by tile at finer levels, and drops any tile whose id
+//! belongs to a request that has since been replaced.
+
+use wasm_bindgen::prelude::*;
+
+use crate::{
+    Diagnostic, ErrorKind,
+    fractal::{Channels, Complex, EscapeGrid, FractalKind, Viewport, check_size},
+};
+
+/// Which iteration counts to color by
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Coloring {
+    /// Integer escape counts
+    Escape,
+    /// Continuous counts, free of banding
+    Smooth,
+}
+
+/// How many pixels along each axis share one sample
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Refinement {
+    Eighth = 8,
+    Quarter = 4,
+    Half = 2,
+    Full = 1,
+}
+
+/// A rectangle of a viewport in pixels, from its top-left corner
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Tile {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+#[wasm_bindgen]
+impl Tile {
+    #[wasm_bindgen(constructor)]
+    pub fn new(x: u32, y: u32, width: u32, height: u32) -> Tile {
+        Tile {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+}
+
+/// One render of a fractal, or of one tile of it
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RenderRequest {
+    /// Chosen by the caller and returned with each tile, so stale tiles can be dropped
+    pub id: u32,
+    pub kind: FractalKind,
+    pub viewport: Viewport,
+    /// The parameter of a Julia set; ignored for the Mandelbrot set
+    pub julia_c: Option<Complex>,
+    pub coloring: Coloring,
+    /// Samples per pixel along each axis at full resolution, 1 or 2
+    pub supersample: u32,
+    /// The part of the viewport to render, or all of it if unset
+    pub tile: Option<Tile>,
+    pub refinement: Refinement,
+}
+
+#[wasm_bindgen]
+impl RenderRequest {
+    /// A request for the whole viewport at full resolution, colored by smooth counts
+    #[wasm_bindgen(constructor)]
+    pub fn new(id: u32, kind: FractalKind, viewport: Viewport) -> RenderRequest {
+        RenderRequest {
+            id,
+            kind,
+            viewport,
+            julia_c: None,
+            coloring: Coloring::Smooth,
+            supersample: 1,
+            tile: None,
+            refinement: Refinement::Full,
+        }
+    }
+}
+
+/// Where a rendered tile belongs
+///
+/// The grid it was rendered into has one cell per `cell` pixels along each
+/// axis, so drawing it scaled by `cell` from `(tile.x, tile.y)` covers the
+/// tile. The last row and column of cells may overhang the tile's right and
+/// bottom edges by less than one cell.
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RenderedTile {
+    /// The id of the request that produced the tile
+    pub id: u32,
+    pub tile: Tile,
+    pub refinement: Refinement,
+    /// Pixels per grid cell along each axis; 1/2 when supersampling
+    pub cell: f64,
+}
+
+/// Render the tile of `request` into `out`
+///
+/// Supersampling applies only at full resolution; coarser levels are previews.
+/// Interior detection is always on, since it only makes points in the set
+/// faster to render.
+#[wasm_bindgen(js_name = renderTile)]
+pub fn render_tile(
+    request: &RenderRequest,
+    out: &mut EscapeGrid,
+) -> Result<RenderedTile, Diagnostic> {
+    let viewport = &request.viewport;
+    viewport.check()?;
+    let tile = (request.tile).unwrap_or(Tile::new(0, 0, viewport.width, viewport.height));
+    let inside = u64::from(tile.x) + u64::from(tile.width) <= u64::from(viewport.width)
+        && u64::from(tile.y) + u64::from(tile.height) <= u64::from(viewport.height);
+    if tile.width == 0 || tile.height == 0 || !inside {
+        return Err(Diagnostic::new(
+            ErrorKind::Argument,
+            "A tile must be a non-empty part of its viewport",
+        ));
+    }
+    let julia_c = match (request.kind, request.julia_c) {
+        (FractalKind::Julia, None) => {
+            return Err(Diagnostic::new(
+                ErrorKind::Argument,
+                "A Julia set render needs julia_c",
+            ));
+        }
+        (_, c) => c.unwrap_or(Complex::new(0.0, 0.0)),
+    };
+    let cell = match (request.refinement, request.supersample) {
+        (Refinement::Full, 1) => 1.0,
+        (Refinement::Full, 2) => 0.5,
+        (_, 1 | 2) => request.refinement as u32 as f64,
+        _ => {
+            return Err(Diagnostic::new(
+                ErrorKind::Argument,
+                "Supersampling must be 1 or 2",
+            ));
+        }
+    };
+    let cells = |pixels: u32| (pixels as f64 / cell).ceil() as u32;
+    let size = (cells(tile.width), cells(tile.height));
+    check_size(size.0, size.1)?;
+
+    let channels = Channels {
+        smooth: request.coloring == Coloring::Smooth,
+        distance: false,
+        interior: true,
+    };
+    out.render(
+        request.kind,
+        julia_c,
+        size,
+        viewport.max_iter,
+        channels,
+        |i, j| {
+            // At full resolution these are exactly the pixel centres of the whole view
+            let x = tile.x as f64 + (i as f64 + 0.5) * cell;
+            let y = tile.y as f64 + (j as f64 + 0.5) * cell;
+            viewport.point(x, y)
+        },
+    );
+    Ok(RenderedTile {
+        id: request.id,
+        tile,
+        refinement: request.refinement,
+        cell,
+    })
+}
+
+/// Split a `width` × `height` view into tiles of at most `size` pixels square, row by row
+#[wasm_bindgen(js_name = tileLayout)]
+pub fn tile_layout(width: u32, height: u32, size: u32) -> Result<Vec<Tile>, Diagnostic> {
+    if size == 0 {
+        return Err(Diagnostic::new(
+            ErrorKind::Argument,
+            "Tiles need a positive size",
+        ));
+    }
+    let mut tiles = Vec::new();
+    for y in (0..height).step_by(size as usize) {
+        for x in (0..width).step_by(size as usize) {
+            tiles.push(Tile::new(x, y, size.min(width - x), size.min(height - y)));
+        }
+    }
+    Ok(tiles)
+}
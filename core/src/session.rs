@@ -0,0 +1,231 @@
+use std::{collections::HashMap, path::Path};
+
+use uiua::{Compiler, Function, Node, Uiua, Value};
+use wasm_bindgen::prelude::*;
+
+use crate::{
+    AlgoResult, Arg, Diagnostic, ErrorKind, Limits, Output, OutputBuffer,
+    limits::Watchdog,
+    modules::{MAIN_PATH, ModuleFs},
+};
+
+/// A compiled Uiua module whose bindings can be called repeatedly
+///
+/// The module and its imports are compiled once per [`reload`](UiuaSession::reload),
+/// so a slider can drive [`call`](UiuaSession::call) every frame without recompiling.
+#[wasm_bindgen]
+pub struct UiuaSession {
+    fs: ModuleFs,
+    uiua: Uiua,
+    bindings: HashMap<String, Binding>,
+    watchdog: Watchdog,
+}
+
+/// A top-level name that can be called
+#[derive(Clone)]
+enum Binding {
+    Func(Function),
+    /// A constant, which is called with no arguments
+    Const(Value),
+}
+
+#[wasm_bindgen]
+impl UiuaSession {
+    /// Create a session with no module loaded that imports from the embedded `uiua-modules/`
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> UiuaSession {
+        let watchdog = Watchdog::default();
+        UiuaSession {
+            fs: ModuleFs::default(),
+            uiua: runtime(&watchdog),
+            bindings: HashMap::new(),
+            watchdog,
+        }
+    }
+
+    /// Apply `limits` to every later reload and call
+    pub fn set_limits(&mut self, limits: &Limits) {
+        self.watchdog.set_limits(limits.clone());
+    }
+
+    /// Add or replace an importable file, e.g. a hot-edited `prelude.ua`
+    ///
+    /// Takes effect on the next [`reload`](UiuaSession::reload).
+    pub fn add_file(&mut self, path: &str, source: String) {
+        self.fs.insert(path, source);
+    }
+
+    /// Compile and run `source`, replacing the current module
+    ///
+    /// Returns a diagnostic if the new source fails, in which case the
+    /// previously loaded module stays active.
+    pub fn reload(&mut self, source: &str) -> Option<Diagnostic> {
+        match load(&self.fs, source, &self.watchdog) {
+            Ok((uiua, bindings)) => {
+                self.uiua = uiua;
+                self.bindings = bindings;
+                None
+            }
+            Err(e) => Some(e),
+        }
+    }
+
+    /// Call the binding `name` with `args` given in call order
+    pub fn call(&mut self, name: &str, args: Vec<Arg>) -> AlgoResult {
+        let res = self.call_impl(name, args);
+        if res.is_err() {
+            self.reset();
+        }
+        res.into()
+    }
+
+    /// Call `name` like [`call`](UiuaSession::call), writing the top result into `out`
+    ///
+    /// `out` keeps its memory between calls, so per-frame calls on large
+    /// plots do not allocate. Only numeric results can be written.
+    pub fn call_into(
+        &mut self,
+        name: &str,
+        args: Vec<Arg>,
+        out: &mut OutputBuffer,
+    ) -> Option<Diagnostic> {
+        match self.call_values(name, args) {
+            Ok(values) => out.fill(&values[0]).err(),
+            Err(e) => {
+                self.reset();
+                Some(e)
+            }
+        }
+    }
+
+    /// Names of the functions and constants bound by the module and its imports
+    pub fn bindings(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.bindings.keys().cloned().collect();
+        names.sort();
+        names
+    }
+}
+
+impl Default for UiuaSession {
+    fn default() -> Self {
+        UiuaSession::new()
+    }
+}
+
+impl UiuaSession {
+    /// Compile and run `code`, leaving its top-level results on the stack
+    pub(crate) fn load(code: &str, limits: Limits) -> Result<Self, Diagnostic> {
+        let fs = ModuleFs::default();
+        let watchdog = Watchdog::default();
+        watchdog.set_limits(limits);
+        let (uiua, bindings) = load(&fs, code, &watchdog)?;
+        Ok(UiuaSession {
+            fs,
+            uiua,
+            bindings,
+            watchdog,
+        })
+    }
+
+    pub(crate) fn call_impl(
+        &mut self,
+        name: &str,
+        args: Vec<Arg>,
+    ) -> Result<Vec<Output>, Diagnostic> {
+        let values = self.call_values(name, args)?;
+        Ok(values.iter().map(Output::from_value).collect())
+    }
+
+    /// Call `name` and take every value it leaves on the stack, top first
+    fn call_values(&mut self, name: &str, args: Vec<Arg>) -> Result<Vec<Value>, Diagnostic> {
+        let binding = self.bindings.get(name).ok_or_else(|| {
+            Diagnostic::new(ErrorKind::Compile, format!("Unknown binding `{name}`"))
+        })?;
+        let arity = match binding {
+            Binding::Func(f) => f.sig.args(),
+            Binding::Const(_) => 0,
+        };
+        if arity != args.len() {
+            return Err(Diagnostic::new(
+                ErrorKind::Argument,
+                format!(
+                    "`{name}` takes {arity} arguments but {} were given",
+                    args.len()
+                ),
+            ));
+        }
+        let values = args
+            .into_iter()
+            .map(Arg::into_value)
+            .collect::<Result<Vec<Value>, _>>()
+            .map_err(|e| Diagnostic::new(ErrorKind::Argument, e))?;
+
+        // Discard anything left by earlier runs, then push the first argument last
+        self.uiua.take_stack();
+        self.uiua.push_all(values.into_iter().rev());
+        self.watchdog.arm();
+        match binding {
+            Binding::Func(f) => {
+                (self.uiua.call(f)).map_err(|e| self.watchdog.diagnose(ErrorKind::Runtime, &e))?
+            }
+            Binding::Const(val) => self.uiua.push(val.clone()),
+        }
+        self.take_values()
+    }
+
+    /// Take every value left on the stack, top first
+    pub(crate) fn take_results(&mut self) -> Result<Vec<Output>, Diagnostic> {
+        let values = self.take_values()?;
+        Ok(values.iter().map(Output::from_value).collect())
+    }
+
+    fn take_values(&mut self) -> Result<Vec<Value>, Diagnostic> {
+        let mut stack = self.uiua.take_stack();
+        if stack.is_empty() {
+            return Err(Diagnostic::new(
+                ErrorKind::Pop,
+                "No result was left on the stack",
+            ));
+        }
+        stack.reverse();
+        Ok(stack)
+    }
+
+    /// Replace the runtime after a failed call, keeping the compiled bindings
+    fn reset(&mut self) {
+        let mut asm = self.uiua.take_asm();
+        asm.root = Node::default();
+        self.uiua = runtime(&self.watchdog);
+        self.watchdog.arm();
+        // An empty root cannot fail, and running it reinstates the assembly
+        _ = self.uiua.run_asm(asm);
+    }
+}
+
+/// A safe Uiua instance (no file system access) that stops when `watchdog` trips
+fn runtime(watchdog: &Watchdog) -> Uiua {
+    Uiua::with_safe_sys().with_interrupt_hook(watchdog.hook())
+}
+
+/// Compile `code` with imports resolved from `fs`, then run its top level
+fn load(
+    fs: &ModuleFs,
+    code: &str,
+    watchdog: &Watchdog,
+) -> Result<(Uiua, HashMap<String, Binding>), Diagnostic> {
+    let mut comp = Compiler::with_backend(fs.clone());
+    comp.load_str_src(code, Path::new(MAIN_PATH))
+        .map_err(|e| Diagnostic::from_uiua(ErrorKind::Compile, &e))?;
+
+    let mut uiua = runtime(watchdog);
+    watchdog.arm();
+    uiua.run_asm(comp.finish())
+        .map_err(|e| watchdog.diagnose(ErrorKind::Runtime, &e))?;
+
+    let functions =
+        (uiua.bound_functions().into_iter()).map(|(name, f)| (name.to_string(), Binding::Func(f)));
+    let constants = (uiua.bound_values().into_iter())
+        .map(|(name, val)| (name.to_string(), Binding::Const(val)));
+    let bindings = functions.chain(constants).collect();
+    Ok((uiua, bindings))
+}
@@ -0,0 +1,186 @@
+//! Maps typed in as expressions, with Jacobians from automatic differentiation
+//!
+//! A [`UserMap`] needs no hand-written derivative: its expressions are
+//! evaluated over [`Dual`] numbers, which carry exact partial derivatives, so
+//! Lyapunov exponents and Newton's method work for any map the user enters.
+
+use wasm_bindgen::prelude::*;
+
+use crate::{
+    Diagnostic, ErrorKind,
+    expr::{Dual, Expr, Func},
+    fixed_point::{self, FixedPoint},
+    lyapunov,
+    maps::{self, Jacobian, State},
+};
+
+/// The state variables, in the order expressions see them
+const VARIABLES: [&str; 2] = ["x", "y"];
+
+/// A 1D map `x → f(x)` or 2D map `(x, y) → (f(x, y), g(x, y))` given as expressions
+#[wasm_bindgen]
+#[derive(Debug, Clone)]
+pub struct UserMap {
+    /// One expression per dimension
+    exprs: Vec<Expr>,
+    params: Vec<String>,
+}
+
+#[wasm_bindgen]
+impl UserMap {
+    /// Parse the map `x → x_expr` or, with `y_expr`, `(x, y) → (x_expr, y_expr)`
+    ///
+    /// `params` names the parameters that later calls pass values for, in order.
+    #[wasm_bindgen(constructor)]
+    pub fn new(
+        x_expr: &str,
+        y_expr: Option<String>,
+        params: Vec<String>,
+    ) -> Result<UserMap, Diagnostic> {
+        for (i, name) in params.iter().enumerate() {
+            let valid = (name.chars().next()).is_some_and(|c| c.is_alphabetic() || c == '_')
+                && name.chars().all(|c| c.is_alphanumeric() || c == '_');
+            let reason = if !valid {
+                "is not a valid name"
+            } else if VARIABLES.contains(&name.as_str()) || Func::is_reserved(name) {
+                "is reserved"
+            } else if params[..i].contains(name) {
+                "is given twice"
+            } else {
+                continue;
+            };
+            return Err(Diagnostic::new(
+                ErrorKind::Argument,
+                format!("Parameter `{name}` {reason}"),
+            ));
+        }
+        let names: Vec<&str> = params.iter().map(String::as_str).collect();
+        let variables = &VARIABLES[..1 + y_expr.is_some() as usize];
+        let mut exprs = vec![Expr::parse(x_expr, variables, &names)?];
+        if let Some(y_expr) = y_expr {
+            let y = Expr::parse(&y_expr, variables, &names).map_err(|mut e| {
+                e.message = format!("In the y expression: {}", e.message);
+                e
+            })?;
+            exprs.push(y);
+        }
+        Ok(UserMap { exprs, params })
+    }
+
+    /// 1 for maps of `x` alone, 2 for maps of `(x, y)`
+    #[wasm_bindgen(getter)]
+    pub fn dimension(&self) -> u32 {
+        self.exprs.len() as u32
+    }
+
+    /// Iterate from `ic` and return the last `lag + 1` of `n + 1` states, like `iterateMap`
+    pub fn iterate(
+        &self,
+        params: Vec<f64>,
+        ic: Vec<f64>,
+        n: u32,
+        lag: u32,
+    ) -> Result<Vec<f64>, Diagnostic> {
+        self.check_params(&params)?;
+        let ic = maps::state(&ic)?;
+        Ok(maps::iterate_with(
+            |state| self.step(state, &params),
+            ic,
+            n,
+            lag,
+        ))
+    }
+
+    /// The Jacobian at `state`, row-major `[∂f/∂x, ∂f/∂y, ∂g/∂x, ∂g/∂y]`
+    ///
+    /// For 1D maps only the first entry, f′(x), is nonzero.
+    #[wasm_bindgen(js_name = jacobianAt)]
+    pub fn jacobian_at(&self, params: Vec<f64>, state: Vec<f64>) -> Result<Vec<f64>, Diagnostic> {
+        self.check_params(&params)?;
+        let j = self.jacobian(maps::state(&state)?, &params);
+        Ok(j.concat())
+    }
+
+    /// Lyapunov spectrum along the orbit from `ic`, like `lyapunovSpectrum`
+    pub fn lyapunov(
+        &self,
+        params: Vec<f64>,
+        ic: Vec<f64>,
+        transient: u32,
+        steps: u32,
+    ) -> Result<Vec<f64>, Diagnostic> {
+        self.check_params(&params)?;
+        let spectrum = lyapunov::spectrum_of(
+            self.dimension(),
+            |s| self.step(s, &params),
+            |s| self.jacobian(s, &params),
+            maps::state(&ic)?,
+            transient,
+            steps,
+        );
+        Ok(spectrum
+            .map(|s| lyapunov::interleave(&s))
+            .unwrap_or_default())
+    }
+
+    /// Distinct fixed points reached by Newton's method from `guesses`
+    ///
+    /// `guesses` are interleaved `[x0, y0, x1, y1, ...]`; guesses that do not
+    /// converge are dropped.
+    #[wasm_bindgen(js_name = fixedPoints)]
+    pub fn fixed_points(
+        &self,
+        params: Vec<f64>,
+        guesses: Vec<f64>,
+    ) -> Result<Vec<FixedPoint>, Diagnostic> {
+        self.check_params(&params)?;
+        if !guesses.len().is_multiple_of(2) {
+            return Err(Diagnostic::new(
+                ErrorKind::Argument,
+                "Guesses must be interleaved [x, y] pairs",
+            ));
+        }
+        Ok(fixed_point::fixed_points(
+            self.dimension(),
+            |s| self.step(s, &params),
+            |s| self.jacobian(s, &params),
+            guesses.chunks_exact(2).map(|g| [g[0], g[1]]),
+        ))
+    }
+}
+
+impl UserMap {
+    fn check_params(&self, params: &[f64]) -> Result<(), Diagnostic> {
+        if params.len() == self.params.len() {
+            return Ok(());
+        }
+        Err(Diagnostic::new(
+            ErrorKind::Argument,
+            format!(
+                "The map takes {} parameters ({}) but {} were given",
+                self.params.len(),
+                self.params.join(", "),
+                params.len()
+            ),
+        ))
+    }
+
+    pub fn step(&self, state: State, params: &[f64]) -> State {
+        let vars = &state[..self.exprs.len()];
+        let mut next = [0.0; 2];
+        for (n, e) in next.iter_mut().zip(&self.exprs) {
+            *n = e.eval(vars, params);
+        }
+        next
+    }
+
+    pub fn jacobian(&self, state: State, params: &[f64]) -> Jacobian {
+        let vars = [Dual::variable(state[0], 0), Dual::variable(state[1], 1)];
+        let vars = &vars[..self.exprs.len()];
+        let mut j = [[0.0; 2]; 2];
+        for (row, e) in j.iter_mut().zip(&self.exprs) {
+            *row = e.eval(vars, params).grad;
+        }
+        j
+    }
+}
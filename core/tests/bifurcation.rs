@@ -0,0 +1,93 @@
+//! Checks bifurcation sweeps against the known cycles of the logistic map
+
+use chaos_engine::{
+    OutputBuffer,
+    bifurcation::{IcPolicy, SweepOptions, bifurcation_sweep},
+};
+
+fn sweep(options: &SweepOptions) -> OutputBuffer {
+    let mut out = OutputBuffer::new();
+    bifurcation_sweep("logistic", vec![3.7], "a", options, &mut out)
+        .unwrap_or_else(|e| panic!("{}", e.message));
+    out
+}
+
+/// The sorted x values sampled for the column at `a`
+fn column(out: &OutputBuffer, a: f64) -> Vec<f64> {
+    let mut xs: Vec<f64> = (out.data().chunks(3))
+        .filter(|row| (row[0] - a).abs() < 1e-12)
+        .map(|row| row[1])
+        .collect();
+    xs.sort_by(f64::total_cmp);
+    xs
+}
+
+/// The two points of the logistic map's period-2 cycle
+fn period_two(a: f64) -> [f64; 2] {
+    let root = ((a + 1.0) * (a - 3.0)).sqrt();
+    [(a + 1.0 - root) / (2.0 * a), (a + 1.0 + root) / (2.0 * a)]
+}
+
+fn assert_close(actual: &[f64], expected: &[f64], tol: f64) {
+    assert_eq!(actual.len(), expected.len(), "{actual:?} vs {expected:?}");
+    for (a, e) in actual.iter().zip(expected) {
+        assert!((a - e).abs() <= tol, "{actual:?} vs {expected:?}");
+    }
+}
+
+#[test]
+fn dedup_keeps_one_copy_of_each_cycle() {
+    let mut options = SweepOptions::new(3.2, 3.5, 0.3);
+    options.ic_x = 0.3;
+    options.dedup_tolerance = Some(1e-9);
+    let out = sweep(&options);
+    assert_eq!(out.shape(), [6, 3]);
+    assert_close(&column(&out, 3.2), &period_two(3.2), 1e-9);
+
+    // At a = 3.5 the attractor is a 4-cycle, so four distinct points that
+    // the map permutes
+    let xs = column(&out, 3.5);
+    assert_eq!(xs.len(), 4);
+    assert!(xs.windows(2).all(|w| w[1] - w[0] > 0.01), "{xs:?}");
+    for &x in &xs {
+        let next = 3.5 * x * (1.0 - x);
+        assert!(xs.iter().any(|&y| (y - next).abs() < 1e-9), "{xs:?}");
+    }
+    assert_close(&xs, &[0.38282, 0.50088, 0.82694, 0.87500], 1e-5);
+
+    // Without dedup every sample is kept, repeating the cycle
+    options.dedup_tolerance = None;
+    options.samples = 64;
+    let out = sweep(&options);
+    assert_eq!(out.shape(), [128, 3]);
+    let xs = column(&out, 3.2);
+    assert_close(&[xs[0], xs[63]], &period_two(3.2), 1e-9);
+}
+
+#[test]
+fn random_initial_conditions_follow_the_seed() {
+    let mut options = SweepOptions::new(3.8, 4.0, 0.01);
+    options.ic_policy = IcPolicy::Random;
+    options.transient = 10;
+    options.samples = 20;
+    options.seed = 7;
+    let first = sweep(&options);
+    let again = sweep(&options);
+    assert_eq!(first.shape(), [21 * 20, 3]);
+    assert_eq!(first.data(), again.data());
+
+    options.seed = 8;
+    assert_ne!(sweep(&options).data(), first.data());
+}
+
+#[test]
+fn cycles_are_found_after_the_transient() {
+    // Just past the first period doubling the orbit lingers by the unstable
+    // fixed point, so it repeats closely long before reaching the 2-cycle
+    let a = 3.02;
+    let mut options = SweepOptions::new(a, a, 0.1);
+    options.ic_x = 1.0 - 1.0 / a + 1e-6;
+    options.dedup_tolerance = Some(1e-6);
+    let out = sweep(&options);
+    assert_close(&column(&out, a), &period_two(a), 1e-6);
+}
@@ -0,0 +1,95 @@
+//! Checks colormaps, scaling and escape-grid coloring
+
+use chaos_engine::color::{ColorOptions, Colormap, RgbaImage, Scale, colorize, colorize_escape};
+use chaos_engine::fractal::{Channels, Complex, EscapeGrid, Viewport, render_mandelbrot};
+
+fn rgb(image: &RgbaImage, i: usize) -> [u8; 3] {
+    let p = &image.pixels()[4 * i..4 * i + 4];
+    assert_eq!(p[3], 255);
+    [p[0], p[1], p[2]]
+}
+
+#[test]
+fn colormaps_interpolate_between_stops() {
+    let viridis = Colormap::named("viridis").unwrap();
+    assert_eq!(viridis.color_at(0.0), "#440154");
+    assert_eq!(viridis.color_at(1.0), "#fde725");
+    let twilight = Colormap::named("twilight").unwrap();
+    assert_eq!(twilight.color_at(0.0), twilight.color_at(1.0));
+    assert!(Colormap::named("jet").is_err());
+
+    let custom = Colormap::new(
+        vec![0.0, 0.5, 1.0],
+        vec!["#000000".into(), "#ff0000".into(), "#ff00ff".into()],
+    )
+    .unwrap();
+    // Colors come from a lookup table, so halfway may round either way
+    let near = |a: [u8; 3], b: [u8; 3]| (0..3).all(|k| a[k].abs_diff(b[k]) <= 1);
+    assert!(near(custom.lookup(0.25), [128, 0, 0]));
+    assert!(near(custom.lookup(0.75), [255, 0, 128]));
+    assert_eq!(custom.lookup(2.0), [255, 0, 255]);
+    assert!(Colormap::new(vec![0.0, 1.0], vec!["#000".into(), "#fff".into()]).is_err());
+    assert!(Colormap::new(vec![1.0, 0.0], vec!["#000000".into(), "#ffffff".into()]).is_err());
+}
+
+#[test]
+fn scales_spread_values_over_the_colormap() {
+    let gray = Colormap::named("grayscale").unwrap();
+    let mut image = RgbaImage::new();
+    let values = [0.0, 1.0, 2.0, 1000.0, f64::NAN, 3.0];
+
+    let mut options = ColorOptions::new();
+    options.fill = "#ff0000".into();
+    colorize(&values, 3, 2, &gray, &options, &mut image).unwrap();
+    assert_eq!((image.width(), image.height()), (3, 2));
+    assert_eq!(rgb(&image, 0), [0, 0, 0]);
+    assert_eq!(rgb(&image, 3), [255, 255, 255]);
+    assert_eq!(rgb(&image, 4), [255, 0, 0]);
+
+    // Ranks ignore the outlier: the five finite values get evenly spaced grays
+    options.scale = Scale::Histogram;
+    colorize(&values, 3, 2, &gray, &options, &mut image).unwrap();
+    let grays: Vec<u8> = [0, 1, 2, 5, 3].map(|i| rgb(&image, i)[0]).to_vec();
+    assert_eq!(grays, [51, 102, 153, 204, 255]);
+
+    options.scale = Scale::Log;
+    options.max = Some(3.0);
+    colorize(&values, 3, 2, &gray, &options, &mut image).unwrap();
+    assert_eq!(rgb(&image, 1)[0], 128); // ln 2 / ln 4
+    assert_eq!(rgb(&image, 5)[0], 255);
+
+    // One pass of the colormap per unit, shifted by a quarter
+    options.scale = Scale::Linear;
+    options.period = Some(1.0 / 3.0);
+    options.offset = 0.25;
+    colorize(&values, 3, 2, &gray, &options, &mut image).unwrap();
+    assert_eq!(rgb(&image, 0), rgb(&image, 1));
+    assert_eq!(rgb(&image, 2)[0], 64);
+
+    assert!(colorize(&values, 2, 2, &gray, &options, &mut image).is_err());
+}
+
+#[test]
+fn escape_grids_fill_the_set() {
+    let viewport = Viewport::new(Complex::new(-0.5, 0.0), 3.0, 30, 20, 100);
+    let mut grid = EscapeGrid::new();
+    let channels = Channels {
+        smooth: true,
+        ..Channels::new()
+    };
+    render_mandelbrot(&viewport, &channels, &mut grid).unwrap();
+
+    let magma = Colormap::named("magma").unwrap();
+    let mut options = ColorOptions::new();
+    options.fill = "#123456".into();
+    let mut image = RgbaImage::new();
+    colorize_escape(&grid, &magma, &options, &mut image).unwrap();
+    assert_eq!(image.pixels().len(), 30 * 20 * 4);
+    for (i, &count) in grid.counts().iter().enumerate() {
+        assert_eq!(
+            rgb(&image, i) == [0x12, 0x34, 0x56],
+            count == 100,
+            "pixel {i}"
+        );
+    }
+}
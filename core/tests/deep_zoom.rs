@@ -0,0 +1,100 @@
+//! Checks perturbation renders against direct iteration
+
+use chaos_engine::deep_zoom::{BigFixed, DeepZoom};
+use chaos_engine::fractal::{Channels, Complex, EscapeGrid, Viewport, render_mandelbrot};
+
+/// Escape count of `re + im·i` by iterating at full precision
+fn escape_count(re: &BigFixed, im: &BigFixed, max_iter: u32) -> u32 {
+    let frac = re.frac_limbs();
+    let (mut x, mut y) = (BigFixed::zero(frac), BigFixed::zero(frac));
+    for n in 0..max_iter {
+        if x.to_f64().powi(2) + y.to_f64().powi(2) > 4.0 {
+            return n;
+        }
+        let xy = &x * &y;
+        (x, y) = (&(&(&x * &x) - &(&y * &y)) + re, &(&xy + &xy) + im);
+    }
+    max_iter
+}
+
+#[test]
+fn fixed_point_numbers_round_trip() {
+    let x = BigFixed::parse("-1.25e-3", 2).unwrap();
+    assert_eq!(x.to_f64(), -0.00125);
+    assert_eq!(x.to_decimal(10), "-0.00125");
+    assert_eq!(BigFixed::from_f64(-0.75, 2).to_decimal(30), "-0.75");
+    let third = &BigFixed::parse("0.1", 3).unwrap() * &BigFixed::parse("3", 3).unwrap();
+    assert_eq!(third.to_decimal(40), "0.3");
+    let sum = &BigFixed::parse("0.25", 2).unwrap() - &BigFixed::parse("1.75", 2).unwrap();
+    assert_eq!(sum.to_decimal(5), "-1.5");
+
+    let digits = "-0.743643887037158704752191506114774";
+    assert_eq!(BigFixed::parse(digits, 3).unwrap().to_decimal(33), digits);
+    assert_eq!(
+        BigFixed::parse("1e-40", 3).unwrap().to_decimal(40),
+        "0.0000000000000000000000000000000000000001"
+    );
+    assert!(BigFixed::parse("0.1.2", 2).is_err());
+    assert!(BigFixed::parse("1e30", 2).is_err());
+}
+
+#[test]
+fn shallow_deep_zooms_match_direct_renders() {
+    let viewport = Viewport::new(Complex::new(-0.75, 0.1), 0.5, 64, 48, 200);
+    let mut direct = EscapeGrid::new();
+    render_mandelbrot(&viewport, &Channels::new(), &mut direct).unwrap();
+
+    let view = DeepZoom::new("-0.75", "0.1", 0.5, 64, 48, 200).unwrap();
+    let mut deep = EscapeGrid::new();
+    view.render(&Channels::new(), &mut deep).unwrap();
+    let same = (direct.counts().iter().zip(deep.counts()))
+        .filter(|(a, b)| a == b)
+        .count();
+    assert!(same * 100 >= 99 * 64 * 48, "{same} pixels agree");
+}
+
+#[test]
+fn deep_zooms_follow_the_reference_orbit() {
+    let (re, im) = (
+        "-0.743643887037158704752191506114774",
+        "0.131825904205311970493132056385139",
+    );
+    let mut view = DeepZoom::new(re, im, 1e-25, 40, 30, 5000).unwrap();
+    let channels = Channels {
+        smooth: true,
+        distance: true,
+        ..Channels::new()
+    };
+    let mut grid = EscapeGrid::new();
+    let stats = view.render(&channels, &mut grid).unwrap();
+    assert!(stats.skipped > 0);
+    assert!(stats.precision_bits >= 128);
+
+    let frac = stats.precision_bits as usize / 64;
+    let (re, im) = (
+        BigFixed::parse(re, frac).unwrap(),
+        BigFixed::parse(im, frac).unwrap(),
+    );
+    for (x, y) in [(0, 0), (39, 0), (20, 15), (7, 22), (39, 29)] {
+        let offset_re = ((x as f64 + 0.5) / 40.0 - 0.5) * 1e-25;
+        let offset_im = -((y as f64 + 0.5) / 30.0 - 0.5) * 0.75e-25;
+        let count = escape_count(
+            &(&re + &BigFixed::from_f64(offset_re, frac)),
+            &(&im + &BigFixed::from_f64(offset_im, frac)),
+            5000,
+        );
+        assert_eq!(
+            grid.counts()[(y * 40 + x) as usize],
+            count,
+            "pixel ({x}, {y})"
+        );
+    }
+    assert!(grid.smooth().iter().all(|s| s.is_finite()));
+    assert!(grid.interior().is_empty());
+
+    // Zooming far past f64 keeps the centre to the digits a pixel needs
+    view.zoom_at(30.0, 10.0, 1e30).unwrap();
+    assert!(view.center_re().len() > 60);
+    view.render(&channels, &mut grid).unwrap();
+    assert_eq!(grid.counts().len(), 40 * 30);
+}
@@ -0,0 +1,107 @@
+//! Checks that typed-in functions of `x` accept the syntax the JS views used
+
+use chaos_engine::{conjugacy::check_conjugacy, curve::Expression, inverse};
+
+#[test]
+fn javascript_syntax_matches_plain_syntax() {
+    for (js, plain) in [
+        ("Math.sin(x) * Math.PI", "sin(x) * pi"),
+        ("x ** 2 ** 0.5", "x ^ (2 ^ 0.5)"),
+        ("Math.exp(-x*x) / Math.E", "exp(-x^2) / exp(1)"),
+        ("4*x*(1-x)", "4*x*(1 - x)"),
+    ] {
+        let (js, plain) = (
+            Expression::new(js).unwrap(),
+            Expression::new(plain).unwrap(),
+        );
+        for x in [0.1, 0.5, 1.7] {
+            let (a, b) = (js.eval(x), plain.eval(x));
+            assert!((a - b).abs() <= 1e-15 * b.abs().max(1.0), "{a} != {b}");
+        }
+    }
+}
+
+#[test]
+fn samples_curves_and_cobwebs() {
+    let f = Expression::new("2 - x*x").unwrap();
+    let curve = f.sample_curve(-2.0, 2.0, 4);
+    assert_eq!(
+        curve,
+        [-2.0, -2.0, -1.0, 1.0, 0.0, 2.0, 1.0, 1.0, 2.0, -2.0]
+    );
+    assert_eq!(f.sample_curve(-2.0, 2.0, 0), [-2.0, -2.0]);
+    assert_eq!(f.eval_many(&[0.0, 1.0, 3.0]), [2.0, 1.0, -7.0]);
+
+    let path = f.cobweb_path(1.0, 2);
+    assert_eq!(path, [1.0, 0.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0]);
+    // An orbit that overflows stops after its first non-finite step
+    let escaping = Expression::new("x*x*1e200").unwrap().cobweb_path(10.0, 50);
+    assert_eq!(escaping.len(), 2 + 4 * 2);
+}
+
+#[test]
+fn errors_give_columns() {
+    for (source, column, message) in [
+        ("", 1, "Expected an expression"),
+        ("Math.sine(x)", 1, "Unsupported function `Math.sine`"),
+        ("x ** * 2", 6, "Expected an expression but found `*`"),
+        ("2 - y", 5, "Unknown name `y`"),
+    ] {
+        let err = Expression::new(source).unwrap_err();
+        assert_eq!(err.message, message, "{source:?}");
+        assert_eq!(err.span.unwrap().column, column, "{source:?}");
+    }
+}
+
+#[test]
+fn checks_conjugacy() {
+    let big_g = Expression::new("4*x*(1-x)").unwrap();
+    let small_g = Expression::new("2 - x*x").unwrap();
+    let check = |c: &str| {
+        let c = Expression::new(c).unwrap();
+        check_conjugacy(&big_g, &small_g, &c, 0.0, 1.0, 100, 1e-12).unwrap()
+    };
+
+    let report = check("4*x - 2");
+    assert!(report.holds());
+    assert!(report.max_error < 1e-13 && report.rms_error <= report.max_error);
+    assert_eq!(report.residuals.len(), 101);
+
+    // The sign-flipped map fails everywhere except near two roots between samples
+    let report = check("2 - 4*x");
+    assert_eq!(report.failures, [0.0, 1.0]);
+    assert_eq!((report.max_error, report.max_at), (4.0, 0.0));
+
+    // Undefined wherever log sees x <= 0.5 on either side, leaving one run that holds
+    let report = check("4*x - 2 + 0*log(x - 0.5)");
+    assert_eq!(report.failures, [0.0, 0.5, 0.86, 1.0]);
+    assert_eq!(report.undefined, 101 - 35);
+    assert!(report.residuals[0].is_nan());
+}
+
+#[test]
+fn inverts_piecewise_monotone_functions() {
+    let invert = |f: &str, y: f64, x_min: f64, x_max: f64| {
+        let f = Expression::new(f).unwrap();
+        inverse::invert(&f, y, x_min, x_max, 100, 1e-13).unwrap()
+    };
+
+    // The logistic map folds [0, 1] at 1/2, so 3/4 has two preimages
+    let logistic = invert("4*x*(1-x)", 0.75, 0.0, 1.0);
+    assert_eq!(logistic.monotone, [0.0, 0.5, 0.5, 1.0]);
+    assert!(!logistic.injective());
+    for (x, expected) in logistic.preimages.iter().zip([0.25, 0.75]) {
+        assert!((x - expected).abs() < 1e-13, "{x}");
+    }
+    assert!(logistic.errors.iter().all(|&e| e < 1e-13));
+
+    // An isolated flat point keeps x³ invertible
+    let cube = invert("x^3", 0.125, -1.0, 1.0);
+    assert!(cube.injective());
+    assert!((cube.preimages[0] - 0.5).abs() < 1e-13);
+
+    // Undefined up to 0 and flat on [0, 0.5], one gap at this sample spacing of 0.02
+    let flat = invert("abs(x) + abs(x - 0.5) + 0*sqrt(x)", 0.8, -1.0, 1.0);
+    assert_eq!(flat.non_invertible, [-1.0, 0.48]);
+    assert!((flat.preimages[0] - 0.65).abs() < 1e-13);
+}
@@ -0,0 +1,178 @@
+//! Checks the escape-time renderers on points whose orbits are known
+
+use chaos_engine::fractal::{
+    Channels, Complex, EscapeGrid, FractalKind, Viewport, escape, julia_boundary, render_julia,
+    render_mandelbrot,
+};
+use chaos_engine::render::{Refinement, RenderRequest, Tile, render_tile, tile_layout};
+
+#[test]
+fn escape_counts_known_orbits() {
+    let origin = Complex::new(0.0, 0.0);
+    // 0 is fixed and -1 is a 2-cycle, so neither escapes
+    assert_eq!(escape(origin, Complex::new(0.0, 0.0), 100), (100, 0.0));
+    assert_eq!(escape(origin, Complex::new(-1.0, 0.0), 100).0, 100);
+    // 0 → 1 → 2 → 5 passes the escape radius on the third step
+    assert_eq!(escape(origin, Complex::new(1.0, 0.0), 100), (3, 25.0));
+
+    // Escaping on the last allowed step still counts as escaping
+    let channels = Channels {
+        smooth: true,
+        distance: true,
+        ..Channels::new()
+    };
+    let samples = [3, 100].map(|max_iter| {
+        let viewport = Viewport::new(Complex::new(1.0, 0.0), 1e-9, 1, 1, max_iter);
+        let mut grid = EscapeGrid::new();
+        render_mandelbrot(&viewport, &channels, &mut grid).unwrap();
+        (grid.counts()[0], grid.smooth()[0], grid.distance()[0])
+    });
+    assert_eq!(samples[0], samples[1]);
+    assert!(samples[1].2 > 0.0 && samples[1].1 != 3.0);
+}
+
+#[test]
+fn mandelbrot_grid_is_symmetric_about_the_real_axis() {
+    let viewport = Viewport::new(Complex::new(-0.5, 0.0), 3.0, 64, 48, 200);
+    let mut grid = EscapeGrid::new();
+    render_mandelbrot(&viewport, &Channels::new(), &mut grid).unwrap();
+    assert_eq!((grid.width(), grid.height()), (64, 48));
+    assert_eq!(grid.counts().len(), 64 * 48);
+
+    let rows: Vec<&[u32]> = grid.counts().chunks(64).collect();
+    for y in 0..24 {
+        assert_eq!(rows[y], rows[47 - y], "row {y}");
+    }
+    // The centre pixel is inside the main cardioid; the corners escape at once
+    assert_eq!(rows[24][32], 200);
+    assert_eq!(rows[0][0], 1);
+
+    let too_big = Viewport::new(Complex::new(0.0, 0.0), 1.0, 1 << 14, 1 << 14, 10);
+    assert!(render_mandelbrot(&too_big, &Channels::new(), &mut grid).is_err());
+}
+
+#[test]
+fn julia_set_of_zero_is_the_unit_circle() {
+    let c = Complex::new(0.0, 0.0);
+    let viewport = Viewport::new(Complex::new(0.0, 0.0), 4.0, 80, 80, 100);
+    let mut grid = EscapeGrid::new();
+    render_julia(&viewport, c, &Channels::new(), &mut grid).unwrap();
+    for y in 0..80 {
+        for x in 0..80 {
+            let z = viewport.pixel(x, y);
+            let inside = z.re * z.re + z.im * z.im < 1.0;
+            let count = grid.counts()[(y * 80 + x) as usize];
+            assert_eq!(count == 100, inside, "pixel ({x}, {y})");
+        }
+    }
+
+    let points = julia_boundary(c, &viewport, 4, 100_000).unwrap();
+    assert!(points.len() > 200);
+    for z in points.chunks(2) {
+        assert!((z[0].hypot(z[1]) - 1.0).abs() < 1e-12);
+    }
+    // No pixel holds more than the hit limit
+    let mut hits = vec![0; 80 * 80];
+    for z in points.chunks(2) {
+        let (x, y) = (
+            ((z[0] + 2.0) * 20.0) as usize,
+            ((2.0 - z[1]) * 20.0) as usize,
+        );
+        hits[y * 80 + x] += 1;
+    }
+    assert!(hits.iter().all(|&h| h <= 4));
+}
+
+#[test]
+fn optional_channels_describe_each_orbit() {
+    let viewport = Viewport::new(Complex::new(-0.5, 0.0), 3.0, 60, 40, 500);
+    let mut grid = EscapeGrid::new();
+    render_mandelbrot(&viewport, &Channels::new(), &mut grid).unwrap();
+    let counts = grid.counts().to_vec();
+    assert!(grid.smooth().is_empty() && grid.interior().is_empty());
+
+    let channels = Channels {
+        smooth: true,
+        distance: true,
+        interior: true,
+    };
+    render_mandelbrot(&viewport, &channels, &mut grid).unwrap();
+    for (i, &count) in counts.iter().enumerate() {
+        let (smooth, distance) = (grid.smooth()[i], grid.distance()[i]);
+        if count < 500 {
+            // ν tracks the integer count, except where an orbit dips back inside radius 2
+            let offset = smooth - count as f64;
+            assert!(
+                count < 2 || (-1.0..2.0).contains(&offset),
+                "pixel {i}: {smooth} vs {count}"
+            );
+            assert!(distance > 0.0);
+            assert_eq!(grid.interior()[i], 0);
+        } else {
+            assert_eq!(distance, 0.0);
+        }
+    }
+    // The centre pixel lies in the main cardioid
+    assert_eq!(grid.interior()[20 * 60 + 30], 1);
+
+    // For c = -2 the set is [-2, 2] on the real line; z = 3i is about 3 away
+    let viewport = Viewport::new(Complex::new(0.0, 3.0), 0.01, 1, 1, 100);
+    let julia = Channels {
+        distance: true,
+        ..Channels::new()
+    };
+    render_julia(&viewport, Complex::new(-2.0, 0.0), &julia, &mut grid).unwrap();
+    let distance = grid.distance()[0];
+    assert!((0.75..=3.0 * 4.0).contains(&distance), "{distance}");
+
+    // For c = -1, 0 lies on an attracting 2-cycle
+    let viewport = Viewport::new(Complex::new(0.0, 0.0), 0.01, 1, 1, 100);
+    render_julia(&viewport, Complex::new(-1.0, 0.0), &channels, &mut grid).unwrap();
+    assert_eq!(grid.interior(), [1]);
+}
+
+#[test]
+fn tiles_reassemble_the_whole_render() {
+    let viewport = Viewport::new(Complex::new(-0.5, 0.0), 3.0, 50, 30, 200);
+    let mut whole = EscapeGrid::new();
+    let channels = Channels {
+        smooth: true,
+        ..Channels::new()
+    };
+    render_mandelbrot(&viewport, &channels, &mut whole).unwrap();
+
+    let mut request = RenderRequest::new(7, FractalKind::Mandelbrot, viewport);
+    let mut tile = EscapeGrid::new();
+    let tiles = tile_layout(50, 30, 16).unwrap();
+    assert_eq!(tiles.len(), 4 * 2);
+    assert_eq!(tiles[7], Tile::new(48, 16, 2, 14));
+    for &t in &tiles {
+        request.tile = Some(t);
+        let rendered = render_tile(&request, &mut tile).unwrap();
+        assert_eq!((rendered.id, rendered.tile, rendered.cell), (7, t, 1.0));
+        for j in 0..t.height {
+            for i in 0..t.width {
+                let k = ((t.y + j) * 50 + t.x + i) as usize;
+                let l = (j * t.width + i) as usize;
+                assert_eq!(tile.counts()[l], whole.counts()[k]);
+                assert_eq!(tile.smooth()[l], whole.smooth()[k]);
+            }
+        }
+    }
+
+    // Coarse levels cover the tile with fewer, larger cells
+    request.tile = Some(Tile::new(8, 0, 20, 30));
+    request.refinement = Refinement::Eighth;
+    let rendered = render_tile(&request, &mut tile).unwrap();
+    assert_eq!(rendered.cell, 8.0);
+    assert_eq!((tile.width(), tile.height()), (3, 4));
+    request.refinement = Refinement::Full;
+    request.supersample = 2;
+    render_tile(&request, &mut tile).unwrap();
+    assert_eq!((tile.width(), tile.height()), (40, 60));
+
+    request.tile = Some(Tile::new(40, 0, 20, 30));
+    assert!(render_tile(&request, &mut tile).is_err());
+    let julia = RenderRequest::new(8, FractalKind::Julia, viewport);
+    assert!(render_tile(&julia, &mut tile).is_err());
+}
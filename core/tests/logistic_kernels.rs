@@ -0,0 +1,94 @@
+//! Checks the native logistic kernels against the Uiua modules they mirror
+//!
+//! The kernels do the same arithmetic in the same order as the Uiua source,
+//! so results must match exactly, even deep into chaotic orbits.
+
+use chaos_engine::{Arg, EMBEDDED_MODULES, Limits, OutputBuffer, logistic};
+
+/// The top result of `name args...` in the embedded `module`
+fn uiua(module: &str, name: &str, args: &[f64]) -> Vec<f64> {
+    let (_, code) = (EMBEDDED_MODULES.iter())
+        .find(|(path, _)| *path == module)
+        .unwrap_or_else(|| panic!("{module} is not embedded"));
+    let args = args.iter().copied().map(Arg::num).collect();
+    let outputs = chaos_engine::call(code, name, args, Limits::new())
+        .unwrap_or_else(|e| panic!("{name}: {}", e.message));
+    outputs.into_iter().next().unwrap().data
+}
+
+/// Compare bit patterns so that NaNs match and -0 differs from 0
+fn assert_same(native: &[f64], reference: &[f64], what: &str) {
+    assert_eq!(native.len(), reference.len(), "{what}: lengths differ");
+    let first_diff = (native.iter().zip(reference)).position(|(a, b)| a.to_bits() != b.to_bits());
+    if let Some(i) = first_diff {
+        panic!(
+            "{what}: element {i} is {} natively but {} in Uiua",
+            native[i], reference[i]
+        );
+    }
+}
+
+#[test]
+fn orbit_matches_uiua() {
+    for (steps, r, x0) in [
+        (1, 3.2, 0.3),
+        (10, 2.8, 0.1),
+        (500, 3.9, 0.2),
+        (200, 4.0, 0.7),
+    ] {
+        assert_same(
+            &logistic::orbit(steps, r, x0),
+            &uiua("logistic.ua", "Orbit", &[steps as f64, r, x0]),
+            &format!("Orbit {steps} {r} {x0}"),
+        );
+    }
+}
+
+#[test]
+fn cobweb_path_matches_uiua() {
+    // `CobwebPath` needs at least one step after x0
+    for (steps, r, x0) in [(2, 3.7, 0.2), (51, 3.7, 0.2), (300, 3.99, 0.5)] {
+        assert_same(
+            &logistic::cobweb_path(steps, r, x0),
+            &uiua("cobweb.ua", "CobwebPath", &[steps as f64, r, x0]),
+            &format!("CobwebPath {steps} {r} {x0}"),
+        );
+    }
+}
+
+#[test]
+fn curve_matches_uiua() {
+    for r in [0.0, 1.5, 3.2, 4.0] {
+        assert_same(
+            &logistic::curve(r, 100, -0.1, 1.1),
+            &uiua("logistic.ua", "LogisticCurve", &[r]),
+            &format!("LogisticCurve {r}"),
+        );
+    }
+}
+
+#[test]
+fn bifurcation_matches_uiua() {
+    let mut out = OutputBuffer::new();
+    for (n, r_min, r_max, transient, keep, x0) in [
+        (1, 3.2, 3.5, 10, 4, 0.3),
+        (20, 2.5, 4.0, 100, 8, 0.5),
+        (200, 3.5, 4.0, 300, 50, 0.2),
+    ] {
+        logistic::bifurcation(&mut out, n, r_min, r_max, transient, keep, x0);
+        let args = [n as f64, r_min, r_max, transient as f64, keep as f64, x0];
+        assert_same(
+            out.data(),
+            &uiua("logistic.ua", "Bifurcation", &args),
+            &format!("Bifurcation {args:?}"),
+        );
+        assert_eq!(out.shape(), [out.len()]);
+    }
+
+    // A single column at r_min, 3.2, which settles onto its 2-cycle
+    logistic::bifurcation(&mut out, 0, 3.2, 3.5, 500, 4, 0.3);
+    let column = out.data();
+    assert_eq!(column.len(), 8);
+    assert!(column.chunks(2).all(|p| p[0] == 3.2));
+    assert!((column[1] - column[5]).abs() < 1e-9 && (column[1] - column[3]).abs() > 0.1);
+}
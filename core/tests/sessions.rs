@@ -0,0 +1,230 @@
+//! Calls through a compiled session, and the results they leave
+
+use chaos_engine::{
+    AlgoResult, Arg, CancelToken, ErrorKind, Limits, OutputBuffer, SourceSpan, UiuaSession,
+    ValueKind,
+};
+
+/// A session with `source` loaded
+fn session(source: &str) -> UiuaSession {
+    let mut session = UiuaSession::new();
+    if let Some(e) = session.reload(source) {
+        panic!("{:?}: {}", e.kind, e.message);
+    }
+    session
+}
+
+#[test]
+fn calls_fill_output_buffers() {
+    let mut s = session(
+        "Grid ← ↯2_3 [0.5 1 2 3 4 5]\n\
+         Mask ← =1 [1 2 1]\n\
+         Roots ← ℂ [1 2] [3 4]\n\
+         Name ← \"logistic\"\n\
+         Nested ← {1 2_3}",
+    );
+    let mut buf = OutputBuffer::new();
+
+    assert!(s.call_into("Grid", vec![], &mut buf).is_none());
+    assert_eq!(buf.kind(), ValueKind::Num);
+    assert_eq!(buf.shape(), [2, 3]);
+    assert_eq!(buf.data(), [0.5, 1.0, 2.0, 3.0, 4.0, 5.0]);
+
+    assert!(s.call_into("Mask", vec![], &mut buf).is_none());
+    assert_eq!(buf.kind(), ValueKind::Byte);
+    assert_eq!(buf.shape(), [3]);
+    assert_eq!(buf.data(), [1.0, 0.0, 1.0]);
+
+    // Complex values are interleaved re/im
+    assert!(s.call_into("Roots", vec![], &mut buf).is_none());
+    assert_eq!(buf.kind(), ValueKind::Complex);
+    assert_eq!(buf.shape(), [2]);
+    assert_eq!(buf.data(), [3.0, 1.0, 4.0, 2.0]);
+
+    // Non-numeric results are refused and leave the buffer as it was
+    for name in ["Name", "Nested"] {
+        let e = s.call_into(name, vec![], &mut buf).unwrap();
+        assert_eq!(e.kind, ErrorKind::UnsupportedValue, "{name}");
+    }
+    assert_eq!(buf.data(), [3.0, 1.0, 4.0, 2.0]);
+
+    // A smaller result reuses the buffer
+    assert!(s.call_into("Grid", vec![], &mut buf).is_none());
+    assert_eq!(buf.len(), 6);
+}
+
+/// The kind and span of the error from running `code`
+fn run_error(code: &str) -> (ErrorKind, Option<SourceSpan>) {
+    let e = chaos_engine::run(code, Limits::new()).unwrap_err();
+    (e.kind, e.span)
+}
+
+fn span(file: Option<&str>, line: u32, column: u32, end_line: u32, end_column: u32) -> SourceSpan {
+    SourceSpan {
+        file: file.map(String::from),
+        line,
+        column,
+        end_line,
+        end_column,
+    }
+}
+
+#[test]
+fn diagnostics_give_kinds_and_spans() {
+    assert_eq!(
+        run_error("X ← 1\nY ← [1 2"),
+        (ErrorKind::Parse, Some(span(None, 2, 8, 2, 9)))
+    );
+    assert_eq!(
+        run_error("X ← 1\nY ← foo 2"),
+        (ErrorKind::Compile, Some(span(None, 2, 5, 2, 8)))
+    );
+    assert_eq!(
+        run_error("X ← 1\n⍤\"bad\" 0"),
+        (ErrorKind::Runtime, Some(span(None, 2, 1, 2, 2)))
+    );
+    assert_eq!(run_error("X ← 1"), (ErrorKind::Pop, None));
+}
+
+#[test]
+fn diagnostics_point_into_imported_files() {
+    let mut s = UiuaSession::new();
+    s.add_file("broken.ua", "Ok ← +1\nBad ← [1 2\n".into());
+    let e = s.reload("~ \"broken.ua\" ~ Ok\nOk 1").unwrap();
+    assert_eq!(e.kind, ErrorKind::Parse);
+    assert_eq!(e.span, Some(span(Some("broken.ua"), 2, 11, 3, 1)));
+
+    s.add_file("broken.ua", "Ok ← +1\nBad ← + [1 2]\n".into());
+    assert!(s.reload("~ \"broken.ua\" ~ Bad").is_none());
+    let e = s
+        .call("Bad", vec![Arg::list(vec![1.0, 2.0, 3.0])])
+        .error
+        .unwrap();
+    assert_eq!(e.kind, ErrorKind::Runtime);
+    assert_eq!(e.message, "Shapes [2] and [3] are not compatible");
+    assert_eq!(e.span, Some(span(Some("broken.ua"), 2, 7, 2, 8)));
+}
+
+#[test]
+fn bad_arguments_are_reported() {
+    let code = "Scale ← ×";
+    let e = chaos_engine::call(code, "Scale", vec![Arg::num(2.0)], Limits::new()).unwrap_err();
+    assert_eq!(e.kind, ErrorKind::Argument);
+    assert_eq!(e.message, "`Scale` takes 2 arguments but 1 were given");
+
+    let grid = Arg::shaped(vec![1.0, 2.0, 3.0], vec![2, 2]);
+    let e =
+        chaos_engine::call(code, "Scale", vec![Arg::num(2.0), grid], Limits::new()).unwrap_err();
+    assert_eq!(e.kind, ErrorKind::Argument);
+    assert_eq!(e.message, "Shape [2, 2] needs 4 elements but 3 were given");
+
+    let grid = Arg::shaped(vec![1.0, 2.0, 3.0, 4.0], vec![2, 2]);
+    let out = chaos_engine::call(code, "Scale", vec![Arg::num(2.0), grid], Limits::new()).unwrap();
+    assert_eq!(out[0].shape, [2, 2]);
+    assert_eq!(out[0].data, [2.0, 4.0, 6.0, 8.0]);
+}
+
+#[test]
+fn failed_reloads_keep_the_old_module() {
+    let mut s = session("Double ← ×2");
+    let e = s.reload("Double ← ×3\nTriple ← [1 2").unwrap();
+    assert_eq!(e.kind, ErrorKind::Parse);
+
+    let res = s.call("Double", vec![Arg::num(5.0)]);
+    assert!(res.error.is_none());
+    assert_eq!(res.value().unwrap().data, [10.0]);
+    assert_eq!(s.bindings(), ["Double"]);
+
+    // A later good reload still replaces it
+    assert!(s.reload("Double ← ×3").is_none());
+    assert_eq!(
+        s.call("Double", vec![Arg::num(5.0)]).value().unwrap().data,
+        [15.0]
+    );
+}
+
+#[test]
+fn outputs_convert_every_value_kind() {
+    let res: AlgoResult = chaos_engine::run(
+        "$orbit [0.5 0.25]\n{1 \"ab\"}\n\"hi\"\nℂ [2 4] [1 3]",
+        Limits::new(),
+    )
+    .into();
+    // Values come back top first
+    let [roots, text, boxed, orbit] = &res.values[..] else {
+        panic!("expected four values, got {}", res.values.len());
+    };
+
+    assert_eq!(roots.kind, ValueKind::Complex);
+    assert_eq!(roots.shape, [2]);
+    assert_eq!(roots.data, [1.0, 2.0, 3.0, 4.0]);
+    assert_eq!(
+        (roots.real(), roots.imag()),
+        (vec![1.0, 3.0], vec![2.0, 4.0])
+    );
+
+    assert_eq!(text.kind, ValueKind::Char);
+    assert_eq!(text.text.as_deref(), Some("hi"));
+    assert!(text.data.is_empty());
+
+    assert_eq!(boxed.kind, ValueKind::Box);
+    assert_eq!(boxed.shape, [2]);
+    assert_eq!(boxed.items[0].data, [1.0]);
+    assert_eq!(boxed.items[1].text.as_deref(), Some("ab"));
+
+    assert_eq!(orbit.kind, ValueKind::Num);
+    assert_eq!(orbit.data, [0.5, 0.25]);
+    assert_eq!(res.names(), ["orbit"]);
+    assert_eq!(res.named("orbit").unwrap().data, [0.5, 0.25]);
+    assert!(res.named("missing").is_none());
+    assert_eq!(res.value().unwrap().kind, ValueKind::Complex);
+}
+
+const FOREVER: &str = "⍢(+1|1) 0";
+
+#[test]
+fn limits_stop_infinite_loops() {
+    let mut limits = Limits::new();
+    limits.time_limit_ms = Some(50.0);
+    let e = chaos_engine::run(FOREVER, limits).unwrap_err();
+    assert_eq!(e.kind, ErrorKind::Timeout);
+    assert_eq!(e.message, "Time limit of 50ms exceeded");
+
+    let mut limits = Limits::new();
+    limits.instruction_budget = Some(10_000);
+    let e = chaos_engine::run(FOREVER, limits).unwrap_err();
+    assert_eq!(e.kind, ErrorKind::Timeout);
+    assert_eq!(e.message, "Instruction budget of 10000 exceeded");
+}
+
+#[test]
+fn cancel_tokens_stop_runs() {
+    let token = CancelToken::new();
+    let mut limits = Limits::new();
+    // In case cancelling fails, so the test ends with the wrong kind rather than hanging
+    limits.time_limit_ms = Some(10_000.0);
+    limits.set_cancel(&token);
+
+    let canceller = {
+        let token = token.clone();
+        std::thread::spawn(move || {
+            std::thread::sleep(std::time::Duration::from_millis(50));
+            token.cancel();
+        })
+    };
+    let e = chaos_engine::run(FOREVER, limits.clone()).unwrap_err();
+    canceller.join().unwrap();
+    assert_eq!(e.kind, ErrorKind::Cancelled);
+    assert_eq!(e.message, "Run was cancelled");
+
+    // A session stays usable after a cancelled call once the token is reset
+    let mut s = session("Forever ← ⍢(+1|1)\nDouble ← ×2");
+    s.set_limits(&limits);
+    let e = s.call("Forever", vec![Arg::num(0.0)]).error.unwrap();
+    assert_eq!(e.kind, ErrorKind::Cancelled);
+    token.reset();
+    assert_eq!(
+        s.call("Double", vec![Arg::num(4.0)]).value().unwrap().data,
+        [8.0]
+    );
+}
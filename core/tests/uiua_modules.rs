@@ -0,0 +1,210 @@
+//! Runs every module in `uiua-modules/` through the engine and checks golden outputs
+//!
+//! Modules run exactly as `run_algo` runs them in the browser, so a failing
+//! `⍤` assertion anywhere in a module or its imports fails `cargo test`.
+//!
+//! Golden tests live in `tests/golden/*.golden`, one call per file:
+//!
+//! ```text
+//! module = cobweb.ua
+//! call = CobwebPath
+//! args = 5 3.7 0.2
+//! tolerance = 1e-12
+//! shape = 18
+//! data = 0.2 0 0.2 0.592 ...
+//! ```
+//!
+//! Elements match when `|actual - expected| <= tolerance * max(1, |expected|)`.
+//! Run with `UPDATE_GOLDEN=1` to rewrite `shape` and `data` from the current output
+//! (comments in the file are not kept).
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use chaos_engine::{Arg, Diagnostic, EMBEDDED_MODULES, ErrorKind, Limits};
+
+/// Generous enough for debug builds, short enough to catch runaway loops
+const TIME_LIMIT_MS: f64 = 10_000.0;
+
+fn modules_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("../uiua-modules")
+}
+
+fn limits() -> Limits {
+    let mut limits = Limits::new();
+    limits.time_limit_ms = Some(TIME_LIMIT_MS);
+    limits
+}
+
+/// Files with the given extension in `dir`, sorted by name
+fn files(dir: &Path, ext: &str) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = fs::read_dir(dir)
+        .unwrap_or_else(|e| panic!("Cannot read {}: {e}", dir.display()))
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|e| e == ext))
+        .collect();
+    files.sort();
+    files
+}
+
+/// `file:line:col: message`, with spans in the module itself attributed to `file`
+fn describe(file: &str, e: &Diagnostic) -> String {
+    match &e.span {
+        Some(span) => {
+            let file = span.file.as_deref().unwrap_or(file);
+            format!("{file}:{}:{}: {}", span.line, span.column, e.message)
+        }
+        None => format!("{file}: {:?}: {}", e.kind, e.message),
+    }
+}
+
+#[test]
+fn module_assertions_hold() {
+    let mut failures = Vec::new();
+    for path in files(&modules_dir(), "ua") {
+        let name = path.file_name().unwrap().to_string_lossy().into_owned();
+        let code = fs::read_to_string(&path).unwrap();
+        match chaos_engine::run(&code, limits()) {
+            // Most modules only define functions and leave nothing behind
+            Ok(_) => {}
+            Err(e) if e.kind == ErrorKind::Pop => {}
+            Err(e) => failures.push(describe(&name, &e)),
+        }
+    }
+    // A failure in an import is hit once per module that imports it
+    failures.sort();
+    failures.dedup();
+    assert!(failures.is_empty(), "\n{}\n", failures.join("\n"));
+}
+
+#[test]
+fn every_module_is_embedded() {
+    let embedded: Vec<&str> = EMBEDDED_MODULES.iter().map(|(name, _)| *name).collect();
+    for path in files(&modules_dir(), "ua") {
+        let name = path.file_name().unwrap().to_string_lossy().into_owned();
+        assert!(
+            embedded.contains(&name.as_str()),
+            "{name} is missing from EMBEDDED_MODULES in src/modules.rs"
+        );
+    }
+}
+
+/// One golden call, as parsed from a `.golden` file
+struct Golden {
+    /// `key = value` lines in file order, so updates keep the layout
+    entries: Vec<(String, String)>,
+}
+
+impl Golden {
+    fn parse(text: &str) -> Result<Self, String> {
+        let entries = (text.lines())
+            .filter(|line| !line.trim().is_empty() && !line.starts_with('#'))
+            .map(|line| {
+                let (key, value) = (line.split_once('='))
+                    .ok_or_else(|| format!("Expected `key = value`, found {line:?}"))?;
+                Ok((key.trim().to_string(), value.trim().to_string()))
+            })
+            .collect::<Result<_, String>>()?;
+        Ok(Golden { entries })
+    }
+
+    fn get(&self, key: &str) -> Result<&str, String> {
+        (self.entries.iter())
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+            .ok_or_else(|| format!("Missing `{key}`"))
+    }
+
+    fn numbers<T: std::str::FromStr>(&self, key: &str) -> Result<Vec<T>, String> {
+        (self.get(key)?.split_whitespace())
+            .map(|n| {
+                n.parse()
+                    .map_err(|_| format!("Bad number {n:?} in `{key}`"))
+            })
+            .collect()
+    }
+
+    fn set(&mut self, key: &str, value: String) {
+        match self.entries.iter_mut().find(|(k, _)| k == key) {
+            Some(entry) => entry.1 = value,
+            None => self.entries.push((key.into(), value)),
+        }
+    }
+
+    fn render(&self) -> String {
+        (self.entries.iter())
+            .map(|(k, v)| format!("{k} = {v}\n"))
+            .collect()
+    }
+}
+
+fn join<T: ToString>(items: &[T]) -> String {
+    items.iter().map(T::to_string).collect::<Vec<_>>().join(" ")
+}
+
+/// Run one golden file, returning why it failed
+fn check_golden(path: &Path, update: bool) -> Result<(), String> {
+    let mut golden = Golden::parse(&fs::read_to_string(path).unwrap())?;
+    let module = golden.get("module")?;
+    let code = fs::read_to_string(modules_dir().join(module))
+        .map_err(|e| format!("Cannot read module {module}: {e}"))?;
+    let name = golden.get("call")?;
+    let args = golden.numbers::<f64>("args")?.into_iter().map(Arg::num);
+    let outputs = chaos_engine::call(&code, name, args.collect(), limits())
+        .map_err(|e| describe(module, &e))?;
+    let actual = &outputs[0];
+
+    if update {
+        golden.set("shape", join(&actual.shape));
+        golden.set("data", join(&actual.data));
+        fs::write(path, golden.render()).unwrap();
+        return Ok(());
+    }
+
+    let shape = golden.numbers::<usize>("shape")?;
+    if actual.shape != shape {
+        return Err(format!("shape {:?} != expected {shape:?}", actual.shape));
+    }
+    let tolerance = golden.get("tolerance")?;
+    let tolerance: f64 = (tolerance.parse()).map_err(|_| format!("Bad tolerance {tolerance:?}"))?;
+    let expected = golden.numbers::<f64>("data")?;
+    if expected.len() != actual.data.len() {
+        return Err(format!(
+            "{} elements != expected {} in `data`",
+            actual.data.len(),
+            expected.len()
+        ));
+    }
+    let mismatches: Vec<String> = (actual.data.iter().zip(&expected).enumerate())
+        .filter(|&(_, (a, e))| {
+            let same_nan = a.is_nan() && e.is_nan();
+            let close = (a - e).abs() <= tolerance * e.abs().max(1.0);
+            !(same_nan || close)
+        })
+        .map(|(i, (a, e))| format!("  [{i}] {a} != expected {e}"))
+        .collect();
+    if !mismatches.is_empty() {
+        return Err(format!(
+            "{} of {} elements differ beyond {tolerance:e}\n{}",
+            mismatches.len(),
+            expected.len(),
+            mismatches.join("\n")
+        ));
+    }
+    Ok(())
+}
+
+#[test]
+fn golden_outputs_match() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden");
+    let update = std::env::var_os("UPDATE_GOLDEN").is_some();
+    let failures: Vec<String> = (files(&dir, "golden").iter())
+        .filter_map(|path| {
+            let name = path.file_name().unwrap().to_string_lossy();
+            (check_golden(path, update).err()).map(|e| format!("{name}: {e}"))
+        })
+        .collect();
+    assert!(failures.is_empty(), "\n{}\n", failures.join("\n"));
+}
@@ -0,0 +1,96 @@
+//! Checks maps typed in as expressions against the hand-written registry maps
+
+use chaos_engine::{fixed_point::Stability, lyapunov, maps, user_map::UserMap};
+
+fn henon() -> UserMap {
+    UserMap::new(
+        "a - x^2 + b*y",
+        Some("x".into()),
+        vec!["a".into(), "b".into()],
+    )
+    .unwrap()
+}
+
+#[test]
+fn jacobian_matches_registry() {
+    let map = henon();
+    let params = [1.4, 0.3];
+    let jacobian = maps::HENON.jacobian.unwrap();
+    for state in [[0.1, 0.2], [-1.3, 0.7], [0.9, -0.4]] {
+        let expected = jacobian(state, &params);
+        assert_eq!(map.jacobian(state, &params), expected, "at {state:?}");
+        assert_eq!(map.step(state, &params), (maps::HENON.step)(state, &params));
+    }
+}
+
+#[test]
+fn lyapunov_matches_registry() {
+    let params = vec![1.4, 0.3];
+    let native = lyapunov::lyapunov_spectrum("henon", params.clone(), vec![0.1, 0.1], 100, 5000);
+    let typed = henon().lyapunov(params, vec![0.1, 0.1], 100, 5000);
+    assert_eq!(typed.unwrap(), native.unwrap());
+}
+
+#[test]
+fn constant_powers_have_finite_slopes_at_zero() {
+    let map = UserMap::new("x^0 + x^2", None, vec![]).unwrap();
+    assert_eq!(map.jacobian([0.0, 0.0], &[]), [[0.0, 0.0], [0.0, 0.0]]);
+    assert_eq!(map.jacobian([3.0, 0.0], &[]), [[6.0, 0.0], [0.0, 0.0]]);
+}
+
+#[test]
+fn fixed_points_are_classified() {
+    let map = UserMap::new("r*x*(1 - x)", None, vec!["r".into()]).unwrap();
+    let points = map
+        .fixed_points(vec![2.5], vec![0.01, 0.0, 0.9, 0.0])
+        .unwrap();
+    let found: Vec<_> = points.iter().map(|p| (p.x, p.stability)).collect();
+    // 0 is repelling with f'(0) = 2.5; 0.6 attracts with f'(0.6) = -0.5
+    assert_eq!(found.len(), 2);
+    assert_eq!(found[0].1, Stability::Repelling);
+    assert!(found[0].0.abs() < 1e-12);
+    assert_eq!(found[1].1, Stability::Attracting);
+    assert!((found[1].0 - 0.6).abs() < 1e-12);
+    assert!((points[1].eigen_re[0] + 0.5).abs() < 1e-12);
+
+    let points = henon()
+        .fixed_points(vec![1.4, 0.3], vec![0.5, 0.5, -1.0, -1.0])
+        .unwrap();
+    assert!(points.iter().all(|p| p.stability == Stability::Saddle));
+}
+
+#[test]
+fn parse_errors_give_columns() {
+    for (source, column, message) in [
+        ("4*x*(1-x", 9, "Expected `)`"),
+        ("2 - z*z", 5, "Unknown name `z`"),
+        ("x + * 2", 5, "Expected an expression but found `*`"),
+        ("sin x", 1, "`sin` needs an argument in parentheses"),
+        ("x # 1", 3, "Unexpected character `#`"),
+    ] {
+        let err = UserMap::new(source, None, vec![]).unwrap_err();
+        assert_eq!(err.message, message, "{source}");
+        assert_eq!(err.span.unwrap().column, column, "{source}");
+    }
+}
+
+#[test]
+fn lyapunov_exponents_match_known_values() {
+    // The fully chaotic logistic map is conjugate to the tent map, so λ = ln 2
+    for ic in [0.1, 0.3, 0.7] {
+        let s = lyapunov::lyapunov_spectrum("logistic", vec![4.0], vec![ic, 0.0], 100, 10_000);
+        let [lambda, err] = s.unwrap()[..] else {
+            panic!("expected one exponent from {ic}");
+        };
+        let diff = (lambda - 2f64.ln()).abs();
+        assert!(diff <= err, "λ = {lambda} ± {err} from {ic}");
+    }
+
+    // The Hénon Jacobian has determinant -b, so λ1 + λ2 = ln |b|
+    let s = lyapunov::lyapunov_spectrum("henon", vec![1.4, 0.3], vec![0.1, 0.1], 100, 10_000);
+    let [l1, e1, l2, e2] = s.unwrap()[..] else {
+        panic!("expected two exponents");
+    };
+    let diff = (l1 + l2 - 0.3f64.ln()).abs();
+    assert!(diff <= e1 + e2, "λ1 + λ2 = {} ± {}", l1 + l2, e1 + e2);
+}
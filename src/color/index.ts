@@ -0,0 +1,134 @@
+// Colormaps applied by the Rust engine (core/src/color.rs), so every view
+// colors its data the same way. The wasm module must be loaded first with
+// `init()` from '../uiua'.
+import {
+  ColorOptions,
+  Colormap,
+  colorize,
+  colorizeEscape,
+  EscapeGrid,
+  RgbaImage,
+  Scale,
+} from '../pkg/chaos_engine'
+import { engineCall } from '../maps/native'
+
+export { RgbaImage }
+
+export type ColormapName = 'viridis' | 'magma' | 'twilight' | 'grayscale'
+export const COLORMAPS: ColormapName[] = [
+  'viridis',
+  'magma',
+  'twilight',
+  'grayscale',
+]
+
+export type ColorScale = 'linear' | 'log' | 'histogram'
+
+/** A named colormap, or gradient stops as [position in 0..1, '#rrggbb'] */
+export type ColormapSpec = ColormapName | { stops: [number, string][] }
+
+export interface Coloring {
+  colormap: ColormapSpec
+  /** How values spread over the colormap (default 'linear') */
+  scale?: ColorScale
+  /** Value at the start of the colormap; the smallest value by default */
+  min?: number
+  /** Value at the end of the colormap; the largest value by default */
+  max?: number
+  /** Repeat the colormap every `period` of the scaled range (0..1) */
+  period?: number
+  /** Shift of a repeating colormap, as a fraction of one period */
+  offset?: number
+  /** Color for non-finite values and points inside a fractal (default black) */
+  fill?: string
+}
+
+const SCALES: Record<ColorScale, Scale> = {
+  linear: Scale.Linear,
+  log: Scale.Log,
+  histogram: Scale.Histogram,
+}
+
+// Built-in colormaps are kept for the life of the page
+const named = new Map<ColormapName, Colormap>()
+
+function withColormap<T>(spec: ColormapSpec, fn: (map: Colormap) => T): T {
+  if (typeof spec === 'string') {
+    let map = named.get(spec)
+    if (!map) {
+      map = engineCall(() => Colormap.named(spec))
+      named.set(spec, map)
+    }
+    return fn(map)
+  }
+  const map = engineCall(
+    () =>
+      new Colormap(
+        Float64Array.from(spec.stops, ([t]) => t),
+        spec.stops.map(([, color]) => color)
+      )
+  )
+  try {
+    return fn(map)
+  } finally {
+    map.free()
+  }
+}
+
+function withOptions<T>(coloring: Coloring, fn: (o: ColorOptions) => T): T {
+  const options = new ColorOptions()
+  options.scale = SCALES[coloring.scale ?? 'linear']
+  options.min = coloring.min
+  options.max = coloring.max
+  options.period = coloring.period
+  options.offset = coloring.offset ?? 0
+  if (coloring.fill) options.fill = coloring.fill
+  try {
+    return fn(options)
+  } finally {
+    options.free()
+  }
+}
+
+/** Copy the engine's pixels into a new ImageData */
+function toImageData(image: RgbaImage): ImageData {
+  return new ImageData(new Uint8ClampedArray(image.data()), image.width)
+}
+
+/** The color at `t` in [0, 1] of a colormap, e.g. for a legend */
+export function colorAt(spec: ColormapSpec, t: number): string {
+  return withColormap(spec, (map) => map.colorAt(t))
+}
+
+/**
+ * Color a width × height grid of values, row-major from the top-left.
+ * `image` is reused across calls to avoid reallocating in wasm.
+ */
+export function colorizeValues(
+  values: Float64Array,
+  width: number,
+  height: number,
+  coloring: Coloring,
+  image: RgbaImage
+): ImageData {
+  withColormap(coloring.colormap, (map) =>
+    withOptions(coloring, (options) =>
+      engineCall(() => colorize(values, width, height, map, options, image))
+    )
+  )
+  return toImageData(image)
+}
+
+/** Color the last render into `grid`, using its smooth counts if it has them */
+export function colorizeEscapeGrid(
+  grid: EscapeGrid,
+  coloring: Coloring,
+  image: RgbaImage
+): ImageData {
+  withColormap(coloring.colormap, (map) =>
+    withOptions(coloring, (options) =>
+      engineCall(() => colorizeEscape(grid, map, options, image))
+    )
+  )
+  return toImageData(image)
+}
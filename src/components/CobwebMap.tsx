@@ -2,7 +2,8 @@ import { createSignal, createEffect, onMount, onCleanup, Show } from 'solid-js'
 import CanvasPlot, { type Plot } from './CanvasPlot'
 import CanvasExportButton from './CanvasExportButton'
 import Latex from './Latex'
-import { compileExpr, cobwebPath, sampleCurve } from '../utils/cobweb'
+import { init } from '../uiua'
+import { compileExpr, type CompiledExpr } from '../utils/cobweb'
 
 // ---------------------------------------------------------------------------
 // Component
@@ -25,10 +26,9 @@ export default function CobwebMap(props: CobwebMapProps) {
   // Expression state: draft (in-progress edit) vs committed (compiled)
   const [expr, setExpr] = createSignal(props.defaultExpr)
   const [exprDraft, setExprDraft] = createSignal(props.defaultExpr)
-  const [compiledFn, setCompiledFn] = createSignal<
-    ((x: number) => number) | null
-  >(null)
+  const [compiledFn, setCompiledFn] = createSignal<CompiledExpr | null>(null)
   const [exprError, setExprError] = createSignal<string | null>(null)
+  const [ready, setReady] = createSignal(false)
 
   const [x0, setX0] = createSignal(props.defaultX0)
   const [plotWidth, setPlotWidth] = createSignal(600)
@@ -38,8 +38,9 @@ export default function CobwebMap(props: CobwebMapProps) {
   let plotHostRef: HTMLDivElement | undefined
   let plotResizeObserver: ResizeObserver | undefined
 
-  // Recompile whenever expr changes
+  // Recompile whenever expr changes (expressions compile in the engine)
   createEffect(() => {
+    if (!ready()) return
     const result = compileExpr(expr())
     if (result instanceof Error) {
       setCompiledFn(null)
@@ -48,6 +49,7 @@ export default function CobwebMap(props: CobwebMapProps) {
       // Wrap in arrow fn: SolidJS treats a bare function as a functional updater
       setCompiledFn(() => result)
       setExprError(null)
+      onCleanup(() => result.free())
     }
   })
 
@@ -76,6 +78,15 @@ export default function CobwebMap(props: CobwebMapProps) {
     queueMicrotask(updatePlotSize)
   })
 
+  onMount(async () => {
+    try {
+      await init()
+      setReady(true)
+    } catch (err) {
+      console.error('Wasm init error:', err)
+    }
+  })
+
   // ---------------------------------------------------------------------------
   // Draw function — signal reads here are tracked inside CanvasPlot's createEffect
   // ---------------------------------------------------------------------------
@@ -95,7 +106,7 @@ export default function CobwebMap(props: CobwebMapProps) {
     if (!fn) return
 
     // 2. Curve g(x) (green)
-    const curvePoints = sampleCurve(fn, bounds.xMin, bounds.xMax)
+    const curvePoints = fn.curve(bounds.xMin, bounds.xMax)
     ctx.strokeStyle = '#15803d'
     ctx.lineWidth = 2
     ctx.beginPath()
@@ -118,7 +129,7 @@ export default function CobwebMap(props: CobwebMapProps) {
 
     // 3. Cobweb path (red)
     const currentX0 = x0() // tracked — redraws when x0 changes
-    const path = cobwebPath(fn, currentX0, iterations)
+    const path = fn.cobweb(currentX0, iterations)
     ctx.strokeStyle = '#dc2626'
     ctx.lineWidth = 1
     ctx.beginPath()
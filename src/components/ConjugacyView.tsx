@@ -1,11 +1,19 @@
-import { createSignal, createEffect, onMount, onCleanup, Show } from 'solid-js'
+import {
+  createSignal,
+  createEffect,
+  createMemo,
+  onMount,
+  onCleanup,
+  Show,
+} from 'solid-js'
 import CanvasPlot, { type Plot, type Bounds } from './CanvasPlot'
 import Latex from './Latex'
+import { init } from '../uiua'
 import {
   compileExpr,
-  cobwebPath,
-  sampleCurve,
   invertNumerically,
+  verifyConjugacy,
+  type CompiledExpr,
 } from '../utils/cobweb'
 
 // ---------------------------------------------------------------------------
@@ -61,7 +69,7 @@ function ExprInput(props: {
           }
         }}
         spellcheck={false}
-        placeholder={props.placeholder ?? 'JS expression…'}
+        placeholder={props.placeholder ?? 'expression…'}
         class={`${props.width ?? 'w-36'} rounded border border-silver-300 bg-white px-1.5 py-0.5 text-xs font-mono text-silver-800`}
       />
       <Show when={props.error()}>
@@ -79,7 +87,7 @@ function ExprInput(props: {
 
 function CobwebPanel(props: {
   title: string
-  compiledFn: () => ((x: number) => number) | null
+  compiledFn: () => CompiledExpr | null
   x0: () => number | null
   bounds: Bounds
   width: number
@@ -105,7 +113,7 @@ function CobwebPanel(props: {
     if (!fn) return
 
     // Curve
-    const pts = sampleCurve(fn, xMin, xMax)
+    const pts = fn.curve(xMin, xMax)
     ctx.strokeStyle = '#15803d'
     ctx.lineWidth = 2
     ctx.beginPath()
@@ -128,7 +136,7 @@ function CobwebPanel(props: {
     const currentX0 = props.x0()
     if (currentX0 === null || !isFinite(currentX0)) return
 
-    const path = cobwebPath(fn, currentX0, ITERATIONS)
+    const path = fn.cobweb(currentX0, ITERATIONS)
     ctx.strokeStyle = props.cobwebColor
     ctx.lineWidth = 1
     ctx.beginPath()
@@ -175,7 +183,7 @@ function CobwebPanel(props: {
 // ---------------------------------------------------------------------------
 
 function TranslatorPanel(props: {
-  compiledC: () => ((x: number) => number) | null
+  compiledC: () => CompiledExpr | null
   x0G: () => number
   width: number
   height: number
@@ -186,7 +194,7 @@ function TranslatorPanel(props: {
 
     if (fn) {
       // C(x) curve
-      const pts = sampleCurve(fn, C_BOUNDS.xMin, C_BOUNDS.xMax)
+      const pts = fn.curve(C_BOUNDS.xMin, C_BOUNDS.xMax)
       ctx.strokeStyle = '#15803d'
       ctx.lineWidth = 2
       ctx.beginPath()
@@ -262,14 +270,14 @@ function TranslatorPanel(props: {
 // Helpers: manage one expression signal triple
 // ---------------------------------------------------------------------------
 
-function useExpr(initial: string) {
+function useExpr(initial: string, ready: () => boolean) {
   const [expr, setExpr] = createSignal(initial)
-  const [compiledFn, setCompiledFn] = createSignal<
-    ((x: number) => number) | null
-  >(null)
+  const [compiledFn, setCompiledFn] = createSignal<CompiledExpr | null>(null)
   const [error, setError] = createSignal<string | null>(null)
 
+  // Expressions compile in the engine, so wait for wasm to load
   createEffect(() => {
+    if (!ready()) return
     const result = compileExpr(expr())
     if (result instanceof Error) {
       setCompiledFn(null)
@@ -277,6 +285,7 @@ function useExpr(initial: string) {
     } else {
       setCompiledFn(() => result)
       setError(null)
+      onCleanup(() => result.free())
     }
   })
 
@@ -290,11 +299,21 @@ function useExpr(initial: string) {
 // ---------------------------------------------------------------------------
 
 export default function ConjugacyView() {
+  const [ready, setReady] = createSignal(false)
+  onMount(async () => {
+    try {
+      await init()
+      setReady(true)
+    } catch (err) {
+      console.error('Wasm init error:', err)
+    }
+  })
+
   // Expression state for each function
-  const G = useExpr('4*x*(1-x)')
-  const g = useExpr('2 - x*x')
-  const C = useExpr('')
-  const CInv = useExpr('')
+  const G = useExpr('4*x*(1-x)', ready)
+  const g = useExpr('2 - x*x', ready)
+  const C = useExpr('', ready)
+  const CInv = useExpr('', ready)
 
   // Shared x0 — lives in G's coordinate space
   const [x0G, setX0G] = createSignal(0.2)
@@ -308,6 +327,13 @@ export default function ConjugacyView() {
     return isFinite(v) ? v : null
   }
 
+  // How far C∘G = g∘C is from holding across G's domain
+  const conjugacy = createMemo(() => {
+    const [Gfn, gfn, Cfn] = [G.compiledFn(), g.compiledFn(), C.compiledFn()]
+    if (!Gfn || !gfn || !Cfn) return null
+    return verifyConjugacy(Gfn, gfn, Cfn, 0, 1)
+  })
+
   // Clicking g's panel: invert C to find x0G
   const handleGClick = (mathX: number) => {
     const clamped = Math.max(G_BOUNDS.xMin, Math.min(G_BOUNDS.xMax, mathX))
@@ -318,8 +344,18 @@ export default function ConjugacyView() {
     const cfn = C.compiledFn()
     if (!cfn) return
     if (autoInvert()) {
-      const inv = invertNumerically(cfn, mathX, G_BOUNDS.xMin, G_BOUNDS.xMax)
-      if (inv !== null) setX0G(inv)
+      const { preimages } = invertNumerically(
+        cfn,
+        mathX,
+        G_BOUNDS.xMin,
+        G_BOUNDS.xMax
+      )
+      // C may fold over; take the preimage nearest the current x0
+      if (preimages.length === 0) return
+      const nearest = preimages.reduce((best, x) =>
+        Math.abs(x - x0G()) < Math.abs(best - x0G()) ? x : best
+      )
+      setX0G(nearest)
     } else {
       const invFn = CInv.compiledFn()
       if (!invFn) return
@@ -440,7 +476,22 @@ export default function ConjugacyView() {
             <div class="text-amber-600">using explicit C⁻¹</div>
           </Show>
           <Show when={autoInvert() && C.compiledFn() !== null}>
-            <div class="text-silver-400">using bisection for C⁻¹</div>
+            <div class="text-silver-400">using Brent's method for C⁻¹</div>
+          </Show>
+          <Show when={conjugacy()}>
+            {(check) => (
+              <div class={check().holds ? 'text-grass-700' : 'text-red-600'}>
+                max |C∘G − g∘C|: {check().maxError.toExponential(2)}, RMS{' '}
+                {check().rmsError.toExponential(2)}
+                <Show when={!check().holds}>
+                  <div>
+                    fails on {check().failures.length} interval(s), first [
+                    {formatVal(check().failures[0][0])},{' '}
+                    {formatVal(check().failures[0][1])}]
+                  </div>
+                </Show>
+              </div>
+            )}
           </Show>
         </div>
       </div>
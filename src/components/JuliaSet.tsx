@@ -2,24 +2,43 @@ import { createSignal, onCleanup, onMount, Show } from 'solid-js'
 import CanvasPlot, { type Plot } from './CanvasPlot'
 import CanvasExportButton from './CanvasExportButton'
 import Latex from './Latex'
-import { juliaIIM, JULIA_PRESETS } from '../utils/julia'
-// TODO: swap engine — implement Uiua escape-time kernel ("The Texture Blaster"):
-//   Uiua computes escape iterations for every pixel → returns Uint8Array of RGBA values
-//   JS wraps with `new ImageData(rgba, w, h)` → canvas uses `ctx.putImageData()`
-//   See CLAUDE.md §"For Fractals" for the full strategy. Replace `drawPlot` below.
+import { JULIA_PRESETS } from '../utils/julia'
+import { init } from '../uiua'
+import { EscapeGrid, julia, juliaBoundary, type Viewport } from '../fractal'
+import { colorizeEscapeGrid, RgbaImage, type Coloring } from '../color'
 
 const BOUNDS = { xMin: -2, xMax: 2, yMin: -2, yMax: 2 }
-const DEFAULT_NITER = 150_000
+/** Points allowed per pixel by the Modified Inverse Iteration Method */
+const MAX_HITS = 3
+const FILLED_MAX_ITER = 300
+/** Escaping points fade from the background to grass-700, the set's own color */
+const FILLED_COLORING: Coloring = {
+  colormap: {
+    stops: [
+      [0, '#f5f5f4'],
+      [1, '#15803d'],
+    ],
+  },
+  scale: 'log',
+  fill: '#15803d',
+}
+
+type Mode = 'boundary' | 'filled'
 
 export default function JuliaSet() {
   const [a, setA] = createSignal(0)
   const [b, setB] = createSignal(-1)
   const [plotWidth, setPlotWidth] = createSignal(600)
   const [plotHeight, setPlotHeight] = createSignal(600)
+  const [mode, setMode] = createSignal<Mode>('boundary')
+  const [ready, setReady] = createSignal(false)
+  const [pointCount, setPointCount] = createSignal(0)
 
   let exportCardRef: HTMLDivElement | undefined
   let plotHostRef: HTMLDivElement | undefined
   let plotResizeObserver: ResizeObserver | undefined
+  let grid: EscapeGrid | undefined
+  let rgba: RgbaImage | undefined
 
   const updatePlotSize = () => {
     const host = plotHostRef
@@ -31,22 +50,56 @@ export default function JuliaSet() {
     setPlotHeight(size) // square canvas — complex plane is symmetric
   }
 
-  onMount(() => {
+  onMount(async () => {
     plotResizeObserver = new ResizeObserver(() => updatePlotSize())
     if (plotHostRef) plotResizeObserver.observe(plotHostRef)
     onCleanup(() => plotResizeObserver?.disconnect())
+
+    try {
+      await init()
+      grid = new EscapeGrid()
+      rgba = new RgbaImage()
+      setReady(true)
+    } catch (err) {
+      console.error('Wasm init error:', err)
+    }
+  })
+
+  onCleanup(() => {
+    grid?.free()
+    rgba?.free()
   })
 
   const drawPlot = (ctx: CanvasRenderingContext2D, plot: Plot) => {
-    const pts = juliaIIM(a(), b(), DEFAULT_NITER)
+    if (!ready() || !grid || !rgba) return
+    const c = { re: a(), im: b() }
+    const viewport: Viewport = {
+      center: { re: 0, im: 0 },
+      spanRe: BOUNDS.xMax - BOUNDS.xMin,
+      width: plot.width,
+      height: plot.height,
+      maxIter: FILLED_MAX_ITER,
+    }
+
+    if (mode() === 'filled') {
+      julia(viewport, c, grid, { smooth: true })
+      const image = colorizeEscapeGrid(grid, FILLED_COLORING, rgba)
+      const { width, height } = image
+      // drawImage, unlike putImageData, respects the high-DPI scaling
+      const layer = document.createElement('canvas')
+      layer.width = width
+      layer.height = height
+      layer.getContext('2d')?.putImageData(image, 0, 0)
+      ctx.drawImage(layer, 0, 0, plot.width, plot.height)
+      setPointCount(0)
+      return
+    }
 
+    const pts = juliaBoundary(c, viewport, MAX_HITS)
+    setPointCount(pts.length / 2)
     ctx.fillStyle = '#15803d' // grass-700
     for (let i = 0; i < pts.length; i += 2) {
-      const px = plot.toX(pts[i])
-      const py = plot.toY(pts[i + 1])
-      // Skip points outside canvas bounds (can happen for diverging params)
-      if (px < 0 || px > plot.width || py < 0 || py > plot.height) continue
-      ctx.fillRect(px, py, 1, 1)
+      ctx.fillRect(plot.toX(pts[i]), plot.toY(pts[i + 1]), 1, 1)
     }
   }
 
@@ -156,6 +209,17 @@ export default function JuliaSet() {
         onChange={(v) => setB(normalizeB(v))}
         accentClass="accent-red-600"
       />
+      <div class="space-y-1">
+        <div class="text-xs font-mono text-silver-600">view</div>
+        <select
+          class="rounded border border-silver-300 bg-white px-1.5 py-0.5 text-xs font-mono text-silver-700"
+          value={mode()}
+          onChange={(e) => setMode(e.currentTarget.value as Mode)}
+        >
+          <option value="boundary">boundary</option>
+          <option value="filled">filled</option>
+        </select>
+      </div>
       <div class="space-y-1">
         <div class="text-xs font-mono text-silver-600">preset</div>
         <select
@@ -184,11 +248,19 @@ export default function JuliaSet() {
         escape to infinity under iteration.
       </p>
       <p class="mb-3">
-        Visualized here using the{' '}
-        <strong>Inverse Iteration Method</strong>: instead of iterating forward,
-        we apply the inverse map <Latex math="z \mapsto \pm\sqrt{z - c}" />{' '}
-        repeatedly, randomly selecting a branch at each step. The resulting
-        random walk converges to the Julia set boundary.
+        The boundary view uses the{' '}
+        <strong>Modified Inverse Iteration Method</strong>: instead of iterating
+        forward, we apply the inverse map{' '}
+        <Latex math="z \mapsto \pm\sqrt{z - c}" />, which pulls points onto
+        the Julia set, following both branches from a repelling fixed point.
+        Each pixel takes only a few points before the branches through it are
+        dropped, so regions that plain inverse iteration rarely reaches are
+        drawn as densely as the rest.
+      </p>
+      <p class="mb-3">
+        The filled view colours each starting point <Latex math="z_0" /> by
+        how quickly its orbit escapes; points that never escape make up the
+        filled Julia set, whose edge is the Julia set itself.
       </p>
       <p>
         Each choice of <Latex math="c" /> produces a qualitatively different
@@ -234,7 +306,9 @@ export default function JuliaSet() {
                 <Latex math={`f(z) = z^2 + c,\\quad c = ${fmt(a())} ${b() < 0 ? '-' : '+'} ${fmt(Math.abs(b()))}i`} />
               </div>
               <div class="mb-2 text-xs font-mono text-silver-600">
-                {DEFAULT_NITER.toLocaleString()} iterations · IIM
+                {mode() === 'filled'
+                  ? `escape time · ${FILLED_MAX_ITER} iterations`
+                  : `${pointCount().toLocaleString()} points · MIIM`}
               </div>
               <div
                 ref={(el) => {
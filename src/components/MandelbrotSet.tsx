@@ -1,28 +1,232 @@
-import { createSignal, onMount, Show } from 'solid-js'
+import { createEffect, createSignal, onCleanup, onMount, Show } from 'solid-js'
+import Latex from './Latex'
+import { init } from '../uiua'
+import {
+  DEEP_SPAN,
+  zoomDeepView,
+  type DeepView,
+  type Viewport,
+} from '../fractal'
+import {
+  ProgressiveRenderer,
+  type RenderRequest,
+} from '../fractal/progressive'
+import {
+  COLORMAPS,
+  type Coloring,
+  type ColormapName,
+  type ColorScale,
+} from '../color'
+
+// The centre is kept in decimal so deep zooms lose no digits
+const HOME = { center: { re: '-0.5', im: '0' }, spanRe: 3.5 }
+const ITERATION_CHOICES = [100, 250, 500, 1000, 2500, 10_000, 50_000]
 
 export default function MandelbrotSet() {
-  const [initialized, setInitialized] = createSignal(false)
+  const [ready, setReady] = createSignal(false)
   const [status, setStatus] = createSignal('Initializing...')
+  const [center, setCenter] = createSignal(HOME.center)
+  const [spanRe, setSpanRe] = createSignal(HOME.spanRe)
+  const [maxIter, setMaxIter] = createSignal(250)
+  const [coloring, setColoring] =
+    createSignal<RenderRequest['coloring']>('smooth')
+  const [colormap, setColormap] = createSignal<ColormapName>('magma')
+  // Tiles are colored separately, so only scales with a fixed range apply
+  const [scale, setScale] = createSignal<ColorScale>('log')
+  const [plotWidth, setPlotWidth] = createSignal(600)
+  const [renderMs, setRenderMs] = createSignal(0)
+
+  let canvasRef: HTMLCanvasElement | undefined
+  let plotHostRef: HTMLDivElement | undefined
+  let renderer: ProgressiveRenderer | undefined
+
+  const plotHeight = () => Math.round((plotWidth() * 2) / 3)
+
+  const deep = () => spanRe() < DEEP_SPAN
+
+  const viewport = (): Viewport => ({
+    center: { re: Number(center().re), im: Number(center().im) },
+    spanRe: spanRe(),
+    width: plotWidth(),
+    height: plotHeight(),
+    maxIter: maxIter(),
+  })
+
+  const deepView = (): DeepView => ({
+    centerRe: center().re,
+    centerIm: center().im,
+    spanRe: spanRe(),
+    width: plotWidth(),
+    height: plotHeight(),
+    maxIter: maxIter(),
+  })
 
   onMount(async () => {
-    await new Promise((r) => setTimeout(r, 1000))
-    setInitialized(true)
-    setStatus('Initialized')
+    const observer = new ResizeObserver(() => {
+      const w = Math.floor(plotHostRef?.getBoundingClientRect().width ?? 0)
+      if (w > 0) setPlotWidth(Math.max(280, Math.min(900, w)))
+    })
+    if (plotHostRef) observer.observe(plotHostRef)
+    onCleanup(() => observer.disconnect())
+
+    try {
+      await init()
+      setReady(true)
+    } catch (err) {
+      console.error('Wasm init error:', err)
+      setStatus(`Error: ${err}`)
+    }
   })
 
+  onCleanup(() => renderer?.free())
+
+  createEffect(() => {
+    const v = viewport()
+    const view = deepView()
+    const colors: Coloring = {
+      colormap: colormap(),
+      scale: scale(),
+      min: 0,
+      max: maxIter(),
+    }
+    const request: RenderRequest = {
+      kind: 'mandelbrot',
+      viewport: v,
+      coloring: coloring(),
+    }
+    const ctx = canvasRef?.getContext('2d')
+    if (!ready() || !ctx) return
+    renderer ??= new ProgressiveRenderer(ctx, setRenderMs)
+    if (deep()) renderer.renderDeep(view, coloring(), colors)
+    else renderer.render(request, colors)
+  })
+
+  /** Recentre on the clicked point; zoom in, or out with shift held */
+  const onClick = (e: MouseEvent) => {
+    const canvas = e.currentTarget as HTMLCanvasElement
+    const rect = canvas.getBoundingClientRect()
+    const x = ((e.clientX - rect.left) * canvas.width) / rect.width
+    const y = ((e.clientY - rect.top) * canvas.height) / rect.height
+    try {
+      const view = zoomDeepView(deepView(), x, y, e.shiftKey ? 0.5 : 2)
+      setCenter({ re: view.centerRe, im: view.centerIm })
+      setSpanRe(view.spanRe)
+    } catch (err) {
+      // As deep as perturbation can go
+      console.warn(err)
+    }
+  }
+
+  const reset = () => {
+    setCenter(HOME.center)
+    setSpanRe(HOME.spanRe)
+  }
+
+  const fmt = (v: number) => v.toPrecision(6).replace(/\.?0+$/, '')
+
   return (
-    <div class="p-6 bg-silver-50 rounded-lg shadow">
-      <h2 class="text-xl font-bold mb-4">Mandelbrot Set (Fractal)</h2>
-      <Show
-        when={initialized()}
-        fallback={
-          <div class="text-grass-600 font-mono animate-pulse">{status()}</div>
-        }
-      >
-        <div class="h-64 bg-silver-200 rounded flex items-center justify-center text-silver-400">
-          [Canvas Area]
+    <div class="p-4 md:p-5 bg-silver-50 rounded-lg shadow">
+      <h2 class="sr-only">Mandelbrot Set</h2>
+      <div class="p-3 md:p-4 bg-silver-100 border border-silver-300 rounded-lg shadow-sm">
+        <div class="mb-2 flex items-start gap-3">
+          <h3 class="text-lg font-semibold text-silver-900 shrink-0">
+            Mandelbrot Set
+          </h3>
+          <div class="ml-auto flex flex-wrap items-center justify-end gap-2">
+            <label class="text-xs font-mono text-silver-600">
+              colormap{' '}
+              <select
+                class="rounded border border-silver-300 bg-white px-1.5 py-0.5 text-xs font-mono text-silver-700"
+                value={colormap()}
+                onChange={(e) =>
+                  setColormap(e.currentTarget.value as ColormapName)
+                }
+              >
+                {COLORMAPS.map((name) => (
+                  <option value={name}>{name}</option>
+                ))}
+              </select>
+            </label>
+            <label class="text-xs font-mono text-silver-600">
+              scale{' '}
+              <select
+                class="rounded border border-silver-300 bg-white px-1.5 py-0.5 text-xs font-mono text-silver-700"
+                value={scale()}
+                onChange={(e) => setScale(e.currentTarget.value as ColorScale)}
+              >
+                <option value="linear">linear</option>
+                <option value="log">log</option>
+              </select>
+            </label>
+            <label class="text-xs font-mono text-silver-600">
+              coloring{' '}
+              <select
+                class="rounded border border-silver-300 bg-white px-1.5 py-0.5 text-xs font-mono text-silver-700"
+                value={coloring()}
+                onChange={(e) =>
+                  setColoring(
+                    e.currentTarget.value as RenderRequest['coloring']
+                  )
+                }
+              >
+                <option value="escape">escape</option>
+                <option value="smooth">smooth</option>
+              </select>
+            </label>
+            <label class="text-xs font-mono text-silver-600">
+              iterations{' '}
+              <select
+                class="rounded border border-silver-300 bg-white px-1.5 py-0.5 text-xs font-mono text-silver-700"
+                value={maxIter()}
+                onChange={(e) => setMaxIter(parseInt(e.currentTarget.value))}
+              >
+                {ITERATION_CHOICES.map((n) => (
+                  <option value={n}>{n}</option>
+                ))}
+              </select>
+            </label>
+            <button
+              type="button"
+              onClick={reset}
+              class="px-3 py-1.5 rounded bg-grass-700 text-white text-sm font-medium hover:bg-grass-800 transition-colors shadow-sm"
+            >
+              Reset
+            </button>
+          </div>
+        </div>
+        <div class="text-silver-700 mb-2">
+          <Latex math="z_{n+1} = z_n^2 + c,\quad z_0 = 0" />
+        </div>
+        <div class="mb-2 text-xs font-mono text-silver-600">
+          centre {fmt(viewport().center.re)}{' '}
+          {viewport().center.im < 0 ? '-' : '+'}{' '}
+          {fmt(Math.abs(viewport().center.im))}i · width{' '}
+          {spanRe().toExponential(2)}
+          <Show when={deep()}> · perturbation</Show>
+          <Show when={ready()}> · {renderMs().toFixed(0)} ms</Show>
+        </div>
+        <div ref={plotHostRef} class="w-full">
+          <Show
+            when={ready()}
+            fallback={
+              <div class="text-grass-600 font-mono animate-pulse">
+                {status()}
+              </div>
+            }
+          >
+            <canvas
+              ref={canvasRef}
+              width={plotWidth()}
+              height={plotHeight()}
+              onClick={onClick}
+              class="border border-silver-300 rounded bg-white cursor-crosshair"
+            />
+          </Show>
         </div>
-      </Show>
+        <p class="mt-2 text-xs text-silver-600">
+          Click to zoom in on a point, shift-click to zoom out.
+        </p>
+      </div>
     </div>
   )
 }
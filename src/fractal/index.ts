@@ -0,0 +1,240 @@
+// Escape-time fractals rendered by the Rust engine (core/src/fractal.rs).
+// The wasm module must be loaded first with `init()` from '../uiua'.
+import {
+  Channels,
+  Complex as EngineComplex,
+  DeepZoom,
+  EscapeGrid,
+  juliaBoundary as engineJuliaBoundary,
+  renderJulia,
+  renderMandelbrot,
+  Viewport as EngineViewport,
+} from '../pkg/chaos_engine'
+import { engineCall } from '../maps/native'
+
+export { EscapeGrid }
+
+/** Narrower views than this are rendered by perturbation */
+export const DEEP_SPAN = 1e-13
+
+export type Complex = { re: number; im: number }
+
+/** The part of the plane to render, as sketched in NewProject.md */
+export type Viewport = {
+  center: Complex
+  /** Width of the view along the real axis */
+  spanRe: number
+  width: number
+  height: number
+  maxIter: number
+}
+
+/** Per-pixel results, row-major from the top-left */
+export interface EscapeResult {
+  width: number
+  height: number
+  /** Iterations before escape, or maxIter for points taken to be in the set */
+  counts: Uint32Array
+  /** |z|² after the last iteration computed */
+  norms: Float64Array
+  /** Continuous counts ν = n + 1 − log₂ log₂|z|, or maxIter inside the set */
+  smooth?: Float64Array
+  /** Estimated distance to the set, or 0 inside it */
+  distance?: Float64Array
+  /** 1 where a point was shown to lie inside the set */
+  interior?: Uint8Array
+}
+
+/**
+ * A view whose centre is given in decimal to as many digits as its span
+ * needs, for deep zooms (core/src/deep_zoom.rs)
+ */
+export type DeepView = {
+  centerRe: string
+  centerIm: string
+  spanRe: number
+  width: number
+  height: number
+  maxIter: number
+}
+
+/** How a deep zoom render went */
+export type DeepStats = {
+  referenceLength: number
+  /** Iterations skipped by series approximation */
+  skipped: number
+  /** Pixels rebased onto the reference after a glitch or its escape */
+  rebases: number
+  precisionBits: number
+}
+
+/** Optional outputs of a render, each off unless asked for */
+export type ChannelOptions = {
+  smooth?: boolean
+  distance?: boolean
+  interior?: boolean
+}
+
+/** The engine's copy of a viewport, to be freed after use */
+export function engineViewport(v: Viewport): EngineViewport {
+  const center = new EngineComplex(v.center.re, v.center.im)
+  return new EngineViewport(center, v.spanRe, v.width, v.height, v.maxIter)
+}
+
+/** The complex number at pixel (x, y), counting rows down from the top */
+export function pixelToComplex(v: Viewport, x: number, y: number): Complex {
+  const spanIm = (v.spanRe * v.height) / v.width
+  return {
+    re: v.center.re + (x / v.width - 0.5) * v.spanRe,
+    im: v.center.im - (y / v.height - 0.5) * spanIm,
+  }
+}
+
+/**
+ * Render the Mandelbrot set into `grid`. The returned arrays are views into
+ * wasm memory, valid until the next engine call.
+ */
+export function mandelbrot(
+  viewport: Viewport,
+  grid: EscapeGrid,
+  options: ChannelOptions = {}
+): EscapeResult {
+  const v = engineViewport(viewport)
+  const channels = engineChannels(options)
+  try {
+    engineCall(() => renderMandelbrot(v, channels, grid))
+  } finally {
+    v.free()
+    channels.free()
+  }
+  return gridResult(grid, options)
+}
+
+/** Render the filled Julia set of z² + c into `grid`, as for `mandelbrot` */
+export function julia(
+  viewport: Viewport,
+  c: Complex,
+  grid: EscapeGrid,
+  options: ChannelOptions = {}
+): EscapeResult {
+  const v = engineViewport(viewport)
+  const channels = engineChannels(options)
+  try {
+    engineCall(() =>
+      renderJulia(v, new EngineComplex(c.re, c.im), channels, grid)
+    )
+  } finally {
+    v.free()
+    channels.free()
+  }
+  return gridResult(grid, options)
+}
+
+/**
+ * Render a deep zoom of the Mandelbrot set into `grid` by perturbation, as
+ * for `mandelbrot`. There is no `interior` channel.
+ */
+export function deepMandelbrot(
+  view: DeepView,
+  grid: EscapeGrid,
+  options: ChannelOptions = {}
+): { result: EscapeResult; stats: DeepStats } {
+  const zoom = engineDeepZoom(view)
+  const channels = engineChannels({ ...options, interior: false })
+  try {
+    const stats = engineCall(() => zoom.render(channels, grid))
+    const result = gridResult(grid, { ...options, interior: false })
+    const summary = {
+      referenceLength: stats.reference_length,
+      skipped: stats.skipped,
+      rebases: stats.rebases,
+      precisionBits: stats.precision_bits,
+    }
+    stats.free()
+    return { result, stats: summary }
+  } finally {
+    zoom.free()
+    channels.free()
+  }
+}
+
+/** `view` recentred on pixel (x, y) with its span divided by `factor` */
+export function zoomDeepView(
+  view: DeepView,
+  x: number,
+  y: number,
+  factor: number
+): DeepView {
+  const zoom = engineDeepZoom(view)
+  try {
+    engineCall(() => zoom.zoomAt(x, y, factor))
+    return {
+      ...view,
+      centerRe: zoom.centerRe,
+      centerIm: zoom.centerIm,
+      spanRe: zoom.spanRe,
+    }
+  } finally {
+    zoom.free()
+  }
+}
+
+/**
+ * Points spread evenly over the Julia set of z² + c, by the Modified Inverse
+ * Iteration Method: each pixel of `viewport` takes at most `maxHits` points.
+ * Returns interleaved [x0, y0, x1, y1, ...].
+ */
+export function juliaBoundary(
+  c: Complex,
+  viewport: Viewport,
+  maxHits = 4,
+  maxPoints = 200_000
+): Float64Array {
+  const v = engineViewport(viewport)
+  try {
+    return engineCall(() =>
+      engineJuliaBoundary(
+        new EngineComplex(c.re, c.im),
+        v,
+        maxHits,
+        maxPoints
+      )
+    )
+  } finally {
+    v.free()
+  }
+}
+
+function engineDeepZoom(v: DeepView): DeepZoom {
+  return engineCall(
+    () =>
+      new DeepZoom(
+        v.centerRe,
+        v.centerIm,
+        v.spanRe,
+        v.width,
+        v.height,
+        v.maxIter
+      )
+  )
+}
+
+function engineChannels(options: ChannelOptions): Channels {
+  const channels = new Channels()
+  channels.smooth = options.smooth ?? false
+  channels.distance = options.distance ?? false
+  channels.interior = options.interior ?? false
+  return channels
+}
+
+function gridResult(grid: EscapeGrid, options: ChannelOptions): EscapeResult {
+  return {
+    width: grid.width,
+    height: grid.height,
+    counts: grid.counts(),
+    norms: grid.norms(),
+    smooth: options.smooth ? grid.smooth() : undefined,
+    distance: options.distance ? grid.distance() : undefined,
+    interior: options.interior ? grid.interior() : undefined,
+  }
+}
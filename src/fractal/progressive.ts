@@ -0,0 +1,173 @@
+// Progressive, tiled fractal rendering (core/src/render.rs): a coarse preview
+// of the whole view first, then finer levels, then full resolution tile by
+// tile. Work for a request that has been replaced is dropped. Deep zooms are
+// drawn in one pass instead.
+import {
+  Coloring as EngineColoring,
+  Complex as EngineComplex,
+  EscapeGrid,
+  FractalKind,
+  Refinement,
+  RenderRequest as EngineRequest,
+  RgbaImage,
+  renderTile,
+  Tile,
+  tileLayout,
+} from '../pkg/chaos_engine'
+import { engineCall } from '../maps/native'
+import { colorizeEscapeGrid, type Coloring } from '../color'
+import {
+  deepMandelbrot,
+  engineViewport,
+  type Complex,
+  type DeepView,
+  type Viewport,
+} from '.'
+
+/** As in NewProject.md */
+export type RenderRequest = {
+  kind: 'mandelbrot' | 'julia'
+  viewport: Viewport
+  juliaC?: Complex
+  coloring: 'escape' | 'smooth'
+  supersample?: 1 | 2
+}
+
+/** Previews drawn over the whole view before full resolution */
+const PREVIEWS = [Refinement.Eighth, Refinement.Quarter, Refinement.Half]
+const TILE_SIZE = 128
+
+/**
+ * Draws renders onto a canvas a step at a time, yielding to the browser
+ * between steps so panning and zooming stay responsive.
+ */
+export class ProgressiveRenderer {
+  private grid = new EscapeGrid()
+  private image = new RgbaImage()
+  private layer = document.createElement('canvas')
+  private id = 0
+
+  constructor(
+    private ctx: CanvasRenderingContext2D,
+    private onDone?: (ms: number) => void
+  ) {}
+
+  /**
+   * Start drawing `request`, abandoning any render still in progress.
+   *
+   * Each tile is colored on its own, so for tiles to match their neighbours
+   * `colors` needs a fixed `min` and `max` and must not use histogram scaling.
+   */
+  render(request: RenderRequest, colors: Coloring) {
+    const id = ++this.id
+    const { width, height } = request.viewport
+    const steps: [Refinement, Tile | undefined][] = PREVIEWS.map((r) => [
+      r,
+      undefined,
+    ])
+    for (const tile of engineCall(() => tileLayout(width, height, TILE_SIZE)))
+      steps.push([Refinement.Full, tile])
+
+    const start = performance.now()
+    const step = () => {
+      if (id !== this.id) {
+        // A newer request has taken over
+        for (const [, tile] of steps) tile?.free()
+        return
+      }
+      const next = steps.shift()
+      if (!next) {
+        this.onDone?.(performance.now() - start)
+        return
+      }
+      this.draw(id, request, colors, ...next)
+      setTimeout(step, 0)
+    }
+    step()
+  }
+
+  /**
+   * Draw a deep zoom of the Mandelbrot set in one pass, abandoning any render
+   * still in progress. The browser gets a turn first, so a newer request can
+   * replace this one before it starts.
+   */
+  renderDeep(
+    view: DeepView,
+    coloring: RenderRequest['coloring'],
+    colors: Coloring
+  ) {
+    const id = ++this.id
+    setTimeout(() => {
+      if (id !== this.id) return
+      const start = performance.now()
+      deepMandelbrot(view, this.grid, { smooth: coloring === 'smooth' })
+      const pixels = colorizeEscapeGrid(this.grid, colors, this.image)
+      this.ctx.putImageData(pixels, 0, 0)
+      this.onDone?.(performance.now() - start)
+    }, 0)
+  }
+
+  /** Stop drawing the current request */
+  cancel() {
+    this.id++
+  }
+
+  free() {
+    this.cancel()
+    this.grid.free()
+    this.image.free()
+  }
+
+  private draw(
+    id: number,
+    request: RenderRequest,
+    colors: Coloring,
+    refinement: Refinement,
+    tile?: Tile
+  ) {
+    const req = new EngineRequest(
+      id,
+      request.kind === 'julia' ? FractalKind.Julia : FractalKind.Mandelbrot,
+      engineViewport(request.viewport)
+    )
+    if (request.juliaC)
+      req.julia_c = new EngineComplex(request.juliaC.re, request.juliaC.im)
+    req.coloring =
+      request.coloring === 'smooth'
+        ? EngineColoring.Smooth
+        : EngineColoring.Escape
+    req.supersample = request.supersample ?? 1
+    req.tile = tile
+    req.refinement = refinement
+    try {
+      const rendered = engineCall(() => renderTile(req, this.grid))
+      const { id: renderedId, cell, tile: drawn } = rendered
+      const { x, y, width, height } = drawn
+      drawn.free()
+      rendered.free()
+      if (renderedId !== this.id) return
+      const pixels = colorizeEscapeGrid(this.grid, colors, this.image)
+      this.layer.width = pixels.width
+      this.layer.height = pixels.height
+      this.layer.getContext('2d')?.putImageData(pixels, 0, 0)
+
+      const ctx = this.ctx
+      ctx.save()
+      ctx.beginPath()
+      ctx.rect(x, y, width, height)
+      ctx.clip()
+      // Previews stay blocky rather than blurred; supersampled tiles are averaged down
+      ctx.imageSmoothingEnabled = cell < 1
+      ctx.drawImage(
+        this.layer,
+        x,
+        y,
+        pixels.width * cell,
+        pixels.height * cell
+      )
+      ctx.restore()
+    } finally {
+      req.free()
+    }
+  }
+}
@@ -0,0 +1,95 @@
+// Maps typed in as expressions, parsed and differentiated by the engine
+// (core/src/user_map.rs), so no derivative has to be written by hand.
+// The wasm module must be loaded first with `init()` from '../uiua'.
+import { Stability, UserMap } from '../pkg/chaos_engine'
+import { engineCall, toSpectrum } from './native'
+import type { LyapunovSpectrum } from './native'
+
+export { Stability }
+
+export interface FixedPointInfo {
+  x: number
+  y: number
+  /** Eigenvalues of the Jacobian at the point */
+  eigenvalues: { re: number; im: number }[]
+  stability: Stability
+}
+
+/** A compiled expression map; call `free()` when it is no longer needed */
+export interface ExpressionMap {
+  dimension: number
+  params: string[]
+  /** Last `lag + 1` of `n + 1` states as interleaved [x0, y0, x1, y1, ...] */
+  iterate(
+    params: Record<string, number>,
+    ic: [number, number],
+    n: number,
+    lag: number
+  ): Float64Array
+  /** Row-major [∂f/∂x, ∂f/∂y, ∂g/∂x, ∂g/∂y] at (x, y) */
+  jacobian(params: Record<string, number>, x: number, y?: number): Float64Array
+  /** f′(x), in the shape of `MapDefinition.lyapunovDerivative` */
+  derivative(x: number, params: Record<string, number>): number
+  lyapunov(
+    params: Record<string, number>,
+    ic: [number, number],
+    transient: number,
+    steps: number
+  ): LyapunovSpectrum | null
+  /** Distinct fixed points reached by Newton's method from each guess */
+  fixedPoints(
+    params: Record<string, number>,
+    guesses: [number, number][]
+  ): FixedPointInfo[]
+  free(): void
+}
+
+/**
+ * Parse `x → xExpr`, or `(x, y) → (xExpr, yExpr)` when `yExpr` is given.
+ * Throws a UiuaError with the column of any syntax error.
+ */
+export function compileMap(
+  xExpr: string,
+  yExpr: string | undefined,
+  params: string[]
+): ExpressionMap {
+  const map = engineCall(() => new UserMap(xExpr, yExpr, params))
+  const values = (p: Record<string, number>) =>
+    Float64Array.from(params, (name) => p[name] ?? 0)
+  const jacobian = (p: Record<string, number>, x: number, y = 0) =>
+    engineCall(() => map.jacobianAt(values(p), Float64Array.of(x, y)))
+
+  return {
+    dimension: map.dimension,
+    params,
+    iterate: (p, ic, n, lag) =>
+      engineCall(() => map.iterate(values(p), Float64Array.from(ic), n, lag)),
+    jacobian,
+    derivative: (x, p) => jacobian(p, x)[0],
+    lyapunov: (p, ic, transient, steps) =>
+      toSpectrum(
+        engineCall(() =>
+          map.lyapunov(values(p), Float64Array.from(ic), transient, steps)
+        )
+      ),
+    fixedPoints: (p, guesses) => {
+      const points = engineCall(() =>
+        map.fixedPoints(values(p), Float64Array.from(guesses.flat()))
+      )
+      return points.map((point) => {
+        const info = {
+          x: point.x,
+          y: point.y,
+          eigenvalues: Array.from(point.eigen_re, (re, i) => ({
+            re,
+            im: point.eigen_im[i],
+          })),
+          stability: point.stability,
+        }
+        point.free()
+        return info
+      })
+    },
+    free: () => map.free(),
+  }
+}
@@ -2,6 +2,21 @@ import type { MapDefinition } from './types'
 import { henon } from './henon'
 
 export type { MapDefinition } from './types'
+export {
+  nativeMaps,
+  iterateNative,
+  sweepNative,
+  lyapunovNative,
+  lyapunovSweepNative,
+} from './native'
+export { compileMap, Stability } from './expression'
+export type { ExpressionMap, FixedPointInfo } from './expression'
+export type {
+  NativeMap,
+  NativeParam,
+  SweepRequest,
+  LyapunovSpectrum,
+} from './native'
 
 /** All registered maps, keyed by id */
 export const maps: Record<string, MapDefinition> = {
@@ -0,0 +1,211 @@
+// Maps defined once in the Rust engine (core/src/maps.rs) and iterated natively.
+// The wasm module must be loaded first with `init()` from '../uiua'.
+import {
+  bifurcationSweep,
+  Diagnostic,
+  IcPolicy,
+  iterateMap,
+  listMaps,
+  lyapunovSpectrum,
+  lyapunovSweep,
+  OutputBuffer,
+  SweepOptions,
+} from '../pkg/chaos_engine'
+import { UiuaError } from '../uiua/wasm'
+
+export interface NativeParam {
+  name: string
+  default: number
+  min: number
+  max: number
+}
+
+/** Metadata for a map in the engine's registry */
+export interface NativeMap {
+  id: string
+  name: string
+  /** 1 for maps of x alone (y stays 0), 2 for maps of (x, y) */
+  dimension: number
+  /** Parameters in the order the engine expects them */
+  params: NativeParam[]
+  bounds: { xMin: number; xMax: number; yMin: number; yMax: number }
+  hasJacobian: boolean
+}
+
+let registry: NativeMap[] | null = null
+
+/** Every map registered in the engine */
+export function nativeMaps(): NativeMap[] {
+  registry ??= listMaps().map((info) => {
+    const map = {
+      id: info.id,
+      name: info.name,
+      dimension: info.dimension,
+      params: info.params.map((p) => {
+        const param = {
+          name: p.name,
+          default: p.default,
+          min: p.min,
+          max: p.max,
+        }
+        p.free()
+        return param
+      }),
+      bounds: {
+        xMin: info.x_min,
+        xMax: info.x_max,
+        yMin: info.y_min,
+        yMax: info.y_max,
+      },
+      hasJacobian: info.has_jacobian,
+    }
+    info.free()
+    return map
+  })
+  return registry
+}
+
+/** Params in engine order, with defaults for any not given */
+export function paramList(
+  id: string,
+  params: Record<string, number>
+): Float64Array {
+  const map = nativeMaps().find((m) => m.id === id)
+  if (!map) throw new Error(`Unknown map ${id}`)
+  return Float64Array.from(map.params, (p) => params[p.name] ?? p.default)
+}
+
+/** Rethrow engine diagnostics as UiuaError so callers handle one error type */
+export function engineCall<T>(fn: () => T): T {
+  try {
+    return fn()
+  } catch (e) {
+    if (e instanceof Diagnostic) throw new UiuaError(e)
+    throw e
+  }
+}
+
+/**
+ * Iterate map `id` from `ic` for `n` steps, returning the last `lag + 1` states
+ * as interleaved [x0, y0, x1, y1, ...]. Shorter if the orbit escapes.
+ */
+export function iterateNative(
+  id: string,
+  params: Record<string, number>,
+  ic: [number, number],
+  n: number,
+  lag: number
+): Float64Array {
+  const list = paramList(id, params)
+  return engineCall(() => iterateMap(id, list, Float64Array.from(ic), n, lag))
+}
+
+export interface SweepRequest {
+  paramMin: number
+  paramMax: number
+  dParam: number
+  /** Iterates discarded before sampling (default 1000) */
+  transient?: number
+  /** Points kept per column (default 256) */
+  samples?: number
+  /** Fixed initial condition; ignored when `randomIC` is set */
+  ic?: [number, number]
+  /** Fresh random x₀ ∈ (0,1) per column, like `MapDefinition.randomIC` */
+  randomIC?: boolean
+  seed?: number
+  /** Keep one copy of each periodic orbit, matching states to this distance */
+  dedupTolerance?: number
+}
+
+function sweepOptions(request: SweepRequest): SweepOptions {
+  const options = new SweepOptions(
+    request.paramMin,
+    request.paramMax,
+    request.dParam
+  )
+  if (request.transient !== undefined) options.transient = request.transient
+  if (request.samples !== undefined) options.samples = request.samples
+  if (request.ic) [options.ic_x, options.ic_y] = request.ic
+  if (request.randomIC) options.ic_policy = IcPolicy.Random
+  if (request.seed !== undefined) options.seed = request.seed
+  options.dedup_tolerance = request.dedupTolerance
+  return options
+}
+
+/**
+ * Sweep `sweepParam` of map `id` into `out` and return rows of [param, x, y]
+ * as a view into wasm memory (valid until the next engine call).
+ */
+export function sweepNative(
+  id: string,
+  params: Record<string, number>,
+  sweepParam: string,
+  request: SweepRequest,
+  out: OutputBuffer
+): Float64Array {
+  const options = sweepOptions(request)
+  const list = paramList(id, params)
+  try {
+    engineCall(() => bifurcationSweep(id, list, sweepParam, options, out))
+  } finally {
+    options.free()
+  }
+  return out.view()
+}
+
+/** Lyapunov exponents (largest first) and their standard errors */
+export interface LyapunovSpectrum {
+  exponents: number[]
+  errors: number[]
+}
+
+/**
+ * Lyapunov spectrum of map `id` along the orbit from `ic`, averaged over
+ * `steps` after discarding `transient`. Null if the orbit escapes.
+ */
+export function lyapunovNative(
+  id: string,
+  params: Record<string, number>,
+  ic: [number, number],
+  transient: number,
+  steps: number
+): LyapunovSpectrum | null {
+  const list = paramList(id, params)
+  const flat = engineCall(() =>
+    lyapunovSpectrum(id, list, Float64Array.from(ic), transient, steps)
+  )
+  return toSpectrum(flat)
+}
+
+/** Split the engine's [λ1, err1, λ2, err2] into a spectrum; null if empty */
+export function toSpectrum(flat: Float64Array): LyapunovSpectrum | null {
+  if (flat.length === 0) return null
+  const spectrum: LyapunovSpectrum = { exponents: [], errors: [] }
+  for (let i = 0; i < flat.length; i += 2) {
+    spectrum.exponents.push(flat[i])
+    spectrum.errors.push(flat[i + 1])
+  }
+  return spectrum
+}
+
+/**
+ * λ versus `sweepParam`, averaged over `request.samples` steps per column.
+ * Rows are [param, λ1, err1] for 1D maps and [param, λ1, err1, λ2, err2] for
+ * 2D maps, as a view into `out` (valid until the next engine call).
+ */
+export function lyapunovSweepNative(
+  id: string,
+  params: Record<string, number>,
+  sweepParam: string,
+  request: SweepRequest,
+  out: OutputBuffer
+): Float64Array {
+  const options = sweepOptions(request)
+  const list = paramList(id, params)
+  try {
+    engineCall(() => lyapunovSweep(id, list, sweepParam, options, out))
+  } finally {
+    options.free()
+  }
+  return out.view()
+}
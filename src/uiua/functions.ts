@@ -1,38 +1,34 @@
 // Uiua functions for chaos visualizations
 // Each returns interleaved plot data: [x0, y0, x1, y1, ...]
 
-import { run } from './wasm'
+import { call } from './wasm'
 
 // Uiua source files
 import identityCode from '../../uiua-modules/identity.ua?raw'
 import logisticCode from '../../uiua-modules/logistic.ua?raw'
 import cobwebCode from '../../uiua-modules/cobweb.ua?raw'
 
-/** Format number for Uiua (uses ¯ for negative) */
-function uiuaNum(n: number): string {
-  return n < 0 ? `¯${Math.abs(n)}` : String(n)
-}
-
 /** Identity function: y = x for given bounds */
 export function identity(
   min: number,
   max: number,
   numPoints = 101
 ): Float64Array {
-  // Append call with params after function definitions
-  return run(
-    `${identityCode}\nToPlotData Identity ${numPoints} ${uiuaNum(min)} ${uiuaNum(max)}`
-  )
+  return call('identity.ua', identityCode, 'IdentityPlot', [
+    numPoints,
+    min,
+    max,
+  ]).data
 }
 
 /** Logistic map parabola: y = r*x*(1-x) for x in [0, 1] */
 export function logisticParabola(r: number): Float64Array {
-  return run(`${logisticCode}\nLogisticCurve ${r}`)
+  return call('logistic.ua', logisticCode, 'LogisticCurve', [r]).data
 }
 
 /** Cobweb iteration path for logistic map */
 export function cobweb(r: number, x0: number, iterations = 50): Float64Array {
   const safeIterations = Math.max(1, Math.floor(iterations))
   const steps = safeIterations + 1
-  return run(`${cobwebCode}\nCobwebPath ${steps} ${uiuaNum(r)} ${uiuaNum(x0)}`)
+  return call('cobweb.ua', cobwebCode, 'CobwebPath', [steps, r, x0]).data
 }
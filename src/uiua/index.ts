@@ -12,5 +12,15 @@
 // Example: if we later split functions.ts into logistic.ts and mandelbrot.ts,
 // consumers still just `import { cobweb } from '../uiua'` - no changes needed.
 
-export { init } from './wasm'
+export { init, callInto, UiuaError } from './wasm'
+export { OutputBuffer } from '../pkg/chaos_engine'
+export type { UiuaArray, UiuaOutputs } from './wasm'
 export { identity, logisticParabola, cobweb } from './functions'
+
+// Native Rust versions of the logistic modules; same layout and numbers, no interpreter
+export {
+  logisticOrbit,
+  logisticCobweb,
+  logisticCurve,
+  logisticBifurcation,
+} from '../pkg/chaos_engine'
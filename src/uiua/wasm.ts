@@ -1,16 +1,44 @@
 // wasm-bindgen generates this module from our Rust code in core/src/lib.rs
 // - Default export (initWasm): async function that loads & instantiates the .wasm binary
-// - Named export (run_algo): our Rust function exposed via #[wasm_bindgen]
-import initWasm, { run_algo } from '../pkg/chaos_engine'
+// - Named exports (UiuaSession, Arg, OutputBuffer): our Rust API exposed via #[wasm_bindgen]
+import initWasm, {
+  Arg,
+  ErrorKind,
+  Limits,
+  OutputBuffer,
+  UiuaSession,
+  ValueKind,
+} from '../pkg/chaos_engine'
+import type { AlgoResult, Diagnostic, Output } from '../pkg/chaos_engine'
 
-// Uiua prelude: shared utilities automatically in scope for all Uiua code
-// (wasm can't access filesystem, so we prepend this to every run)
+// The engine embeds uiua-modules/ for `~ "prelude.ua"` imports; passing the
+// raw source to sessions as well lets hot-edited prelude changes take effect
 import prelude from '../../uiua-modules/prelude.ua?raw'
 
 // Module-level state: ES modules are singletons, so this is shared
 // across all components that import from this file
 let initialized = false
 
+// One compiled session per module name, with the source it last compiled.
+// Hot-edited modules arrive as new source and are reloaded in place.
+const sessions = new Map<string, { code: string; session: UiuaSession }>()
+
+// A hot update of this file starts a fresh map, so release the old sessions
+import.meta.hot?.dispose(() => {
+  for (const { session } of sessions.values()) session.free()
+  sessions.clear()
+})
+
+// Stop runaway modules (e.g. `⍥Step 1e9`) before they freeze the tab
+const TIME_LIMIT_MS = 2000
+
+/** Fresh limits for one run (the engine takes ownership of the object) */
+function makeLimits(): Limits {
+  const limits = new Limits()
+  limits.time_limit_ms = TIME_LIMIT_MS
+  return limits
+}
+
 /** Initialize the Uiua wasm module. Safe to call multiple times. */
 export async function init(): Promise<void> {
   if (initialized) return
@@ -18,23 +46,155 @@ export async function init(): Promise<void> {
   initialized = true
 }
 
-/** Strip import lines (wasm can't access filesystem, prelude is prepended instead) */
-function stripImports(code: string): string {
-  return code
-    .split('\n')
-    .filter((line) => !line.startsWith('~'))
-    .join('\n')
+/** Error thrown when a Uiua run fails; `diagnostic` locates it in the module source */
+export class UiuaError extends Error {
+  readonly kind: ErrorKind
+  /** Imported file the error is in, or undefined for the module itself */
+  readonly file?: string
+  readonly line?: number
+  readonly column?: number
+
+  constructor(diagnostic: Diagnostic) {
+    super(diagnostic.message)
+    this.name = 'UiuaError'
+    this.kind = diagnostic.kind
+    this.file = diagnostic.span?.file
+    this.line = diagnostic.span?.line
+    this.column = diagnostic.span?.column
+  }
+}
+
+/**
+ * A Uiua array with its shape intact; `data` is row-major.
+ * Complex arrays interleave re/im in `data`, character arrays fill `text`,
+ * and boxed arrays hold one nested array per element in `items`.
+ */
+export interface UiuaArray {
+  kind: ValueKind
+  shape: Uint32Array
+  data: Float64Array
+  text?: string
+  items: UiuaArray[]
+  label?: string
+}
+
+/** Copy an engine output into plain JS objects and release the wasm side */
+function toArray(output: Output): UiuaArray {
+  const array = {
+    kind: output.kind,
+    shape: output.shape,
+    data: output.data,
+    text: output.text,
+    items: output.items.map(toArray),
+    label: output.label,
+  }
+  output.free()
+  return array
+}
+
+/** Every value a Uiua run left on the stack, plus the labelled ones by name */
+export interface UiuaOutputs {
+  /** Stack values, top first */
+  values: UiuaArray[]
+  /** Values labelled in Uiua, e.g. `$orbit` becomes `named.orbit` */
+  named: Record<string, UiuaArray>
 }
 
-/** Run Uiua code and return the result as a Float64Array */
-export function run(code: string): Float64Array {
-  const processed = prelude + '\n' + stripImports(code)
-  const result = run_algo(processed)
+/** Unwrap an engine result, throwing a UiuaError on failure */
+function unwrap(result: AlgoResult, label: string): UiuaOutputs {
+  const { values, error } = result
+  result.free()
   if (import.meta.env.DEV) {
-    console.debug('Uiua:', {
-      code: code.trim().split('\n').pop(),
-      result,
-    })
+    console.debug('Uiua:', { code: label, result: error ?? values })
   }
-  return result
+  if (error) throw new UiuaError(error)
+  const arrays = values.map(toArray)
+  const named: Record<string, UiuaArray> = {}
+  for (const array of arrays) {
+    if (array.label !== undefined && !(array.label in named)) {
+      named[array.label] = array
+    }
+  }
+  return { values: arrays, named }
+}
+
+/** A JS value that can be passed to a Uiua function without formatting it as source */
+export type UiuaArg = number | Float64Array | string | { re: number; im: number }
+
+function toArg(value: UiuaArg): Arg {
+  if (typeof value === 'number') return Arg.num(value)
+  if (typeof value === 'string') return Arg.string(value)
+  if (value instanceof Float64Array) return Arg.list(value)
+  return Arg.complex(value.re, value.im)
+}
+
+/**
+ * Get the compiled session for `module`, compiling it on first use and
+ * reloading it when its source has changed. A source that fails to compile
+ * throws, leaving the previous version loaded.
+ */
+function sessionFor(module: string, code: string): UiuaSession {
+  const entry = sessions.get(module)
+  if (entry) {
+    if (entry.code !== code) {
+      const error = entry.session.reload(code)
+      if (error) throw new UiuaError(error)
+      entry.code = code
+    }
+    return entry.session
+  }
+  const session = new UiuaSession()
+  session.add_file('prelude.ua', prelude)
+  const limits = makeLimits()
+  session.set_limits(limits)
+  limits.free()
+  const error = session.reload(code)
+  if (error) {
+    session.free()
+    throw new UiuaError(error)
+  }
+  sessions.set(module, { code, session })
+  return session
+}
+
+/**
+ * Call the Uiua function `name` defined in `code`, the source of the module
+ * named `module`, with typed arguments. Arguments are in call order:
+ * `call('m.ua', code, 'F', [a, b])` is like `F a b`.
+ */
+export function call(
+  module: string,
+  code: string,
+  name: string,
+  args: UiuaArg[]
+): UiuaArray {
+  return callAll(module, code, name, args).values[0]
+}
+
+/** Like `call`, but return every output of the function rather than just the top one */
+export function callAll(
+  module: string,
+  code: string,
+  name: string,
+  args: UiuaArg[]
+): UiuaOutputs {
+  const result = sessionFor(module, code).call(name, args.map(toArg))
+  return unwrap(result, `${name} ${args.join(' ')}`)
+}
+
+/**
+ * Call `name` and return its top result as a view into `buffer`, without copying.
+ * The view is only valid until the next engine call (memory growth detaches it),
+ * so draw or upload it straight away. Reuse one buffer per plot across frames.
+ */
+export function callInto(
+  module: string,
+  code: string,
+  name: string,
+  args: UiuaArg[],
+  buffer: OutputBuffer
+): Float64Array {
+  const error = sessionFor(module, code).call_into(name, args.map(toArg), buffer)
+  if (error) throw new UiuaError(error)
+  return buffer.view()
 }
@@ -2,88 +2,147 @@
  * Shared pure-math helpers for cobweb diagrams and conjugacy views.
  * No SolidJS, no canvas — just number arrays.
  */
+import { checkConjugacy, Expression, invert } from '../pkg/chaos_engine'
+import { engineCall } from '../maps/native'
+import { UiuaError } from '../uiua/wasm'
+
+/** A function of x parsed by the engine, with batched sampling */
+export interface CompiledExpr {
+  (x: number): number
+  /** n + 1 points of the curve over [xMin, xMax] as flat [x0,y0, x1,y1, ...] */
+  curve(xMin: number, xMax: number, n?: number): Float64Array
+  /**
+   * The cobweb path as a flat [x0,y0, x1,y1, ...] array.
+   * Starting at (x0, 0), then for each step:
+   *   vertical:   (x, prev_y) → (x, f(x))
+   *   horizontal: (x, f(x))  → (f(x), f(x))
+   * Stops after the orbit escapes.
+   */
+  cobweb(x0: number, n: number): Float64Array
+  /** The engine's parsed expression, for routines that take several */
+  readonly expression: Expression
+  /** Release the engine's copy of the expression */
+  free(): void
+}
 
 /**
- * Compile a JS expression string into a callable function.
- * Accepts standard JS math: "2 - x*x", "Math.sin(x)", "4*x*(1-x)"
- * Returns the function on success, or an Error describing the problem.
+ * Compile an expression string in the engine, without running it as JS.
+ * Accepts JS-style math: "2 - x*x", "Math.sin(x)", "4*x*(1-x)", "x**3"
+ * Returns the function on success, or an Error naming the column at fault.
+ * The wasm module must be loaded first with `init()` from '../uiua'.
  */
-export function compileExpr(expr: string): ((x: number) => number) | Error {
+export function compileExpr(expr: string): CompiledExpr | Error {
   if (!expr.trim()) return new Error('empty expression')
+  let parsed: Expression
   try {
-    const fn = new Function('x', `"use strict"; return (${expr});`) as (
-      x: number
-    ) => number
-    fn(0) // test call — catches runtime errors eagerly
-    return fn
+    parsed = engineCall(() => new Expression(expr))
   } catch (e) {
+    if (e instanceof UiuaError && e.column !== undefined)
+      return new Error(`column ${e.column}: ${e.message}`)
     return e instanceof Error ? e : new Error(String(e))
   }
+  return Object.assign((x: number) => parsed.eval(x), {
+    curve: (xMin: number, xMax: number, n = 200) =>
+      parsed.sampleCurve(xMin, xMax, n),
+    cobweb: (x0: number, n: number) => parsed.cobwebPath(x0, n),
+    expression: parsed,
+    free: () => parsed.free(),
+  })
 }
 
-/**
- * Generate the cobweb path as a flat [x0,y0, x1,y1, ...] array.
- * Starting at (x0, 0), then for each step:
- *   vertical:   (x, prev_y) → (x, f(x))
- *   horizontal: (x, f(x))  → (f(x), f(x))
- */
-export function cobwebPath(
-  f: (x: number) => number,
-  x0: number,
-  n: number
-): number[] {
-  const pts: number[] = [x0, 0]
-  let x = x0
-  for (let i = 0; i < n; i++) {
-    const y = f(x)
-    pts.push(x, y) // vertical: up to curve
-    pts.push(y, y) // horizontal: over to diagonal
-    x = y
-    if (!isFinite(x)) break // orbit escaped
-  }
-  return pts
+/** Split the engine's flat [start, end, start, end, ...] into intervals */
+function pairs(flat: Float64Array): [number, number][] {
+  const out: [number, number][] = []
+  for (let i = 0; i < flat.length; i += 2) out.push([flat[i], flat[i + 1]])
+  return out
+}
+
+export interface ConjugacyCheck {
+  /** Sample points and |C(G(x)) − g(C(x))| at each (NaN where undefined) */
+  xs: Float64Array
+  residuals: Float64Array
+  maxError: number
+  maxAt: number
+  rmsError: number
+  /** Samples where either side is not a finite number */
+  undefined: number
+  /** Runs of failing samples as [start, end] intervals */
+  failures: [number, number][]
+  holds: boolean
 }
 
 /**
- * Sample the curve f(x) over [xMin, xMax] as a flat [x0,y0, x1,y1, ...] array.
+ * Check C∘G = g∘C at n + 1 evenly spaced points of [xMin, xMax]. A sample
+ * fails when its residual exceeds tolerance · (1 + |g(C(x))|).
  */
-export function sampleCurve(
-  f: (x: number) => number,
+export function verifyConjugacy(
+  G: CompiledExpr,
+  g: CompiledExpr,
+  C: CompiledExpr,
   xMin: number,
   xMax: number,
-  n = 200
-): number[] {
-  const pts: number[] = []
-  for (let i = 0; i <= n; i++) {
-    const x = xMin + (i / n) * (xMax - xMin)
-    const y = f(x)
-    pts.push(x, y)
+  n = 1000,
+  tolerance = 1e-9
+): ConjugacyCheck {
+  const report = engineCall(() =>
+    checkConjugacy(
+      G.expression,
+      g.expression,
+      C.expression,
+      xMin,
+      xMax,
+      n,
+      tolerance
+    )
+  )
+  const check = {
+    xs: report.xs,
+    residuals: report.residuals,
+    maxError: report.max_error,
+    maxAt: report.max_at,
+    rmsError: report.rms_error,
+    undefined: report.undefined,
+    failures: pairs(report.failures),
+    holds: report.holds,
   }
-  return pts
+  report.free()
+  return check
+}
+
+export interface Preimages {
+  /** Every x with C(x) = y in the interval, ascending, with error bounds */
+  preimages: number[]
+  errors: number[]
+  /** [start, end] intervals where C is strictly monotone */
+  monotone: [number, number][]
+  /** [start, end] intervals where C is undefined or constant */
+  nonInvertible: [number, number][]
+  /** Whether C is one-to-one on the whole interval */
+  injective: boolean
 }
 
 /**
- * Numerically invert a monotone function C on [xMin, xMax] via bisection.
- * Returns null if y is outside C's range on that interval, or if C is
- * non-monotone (bisection sign check fails).
+ * All preimages of y under C on [xMin, xMax], found by splitting C into
+ * monotone pieces and running Brent's method on each.
  */
 export function invertNumerically(
-  C: (x: number) => number,
+  C: CompiledExpr,
   y: number,
   xMin: number,
   xMax: number,
   tol = 1e-10
-): number | null {
-  const flo = C(xMin) - y
-  const fhi = C(xMax) - y
-  if (flo * fhi > 0) return null // y outside range or non-monotone
-  let lo = xMin
-  let hi = xMax
-  for (let i = 0; i < 64; i++) {
-    if (hi - lo < tol) break
-    const mid = (lo + hi) / 2
-    if (C(mid) - y < 0 === flo < 0) lo = mid
-    else hi = mid
+): Preimages {
+  const inversion = engineCall(() =>
+    invert(C.expression, y, xMin, xMax, 1000, tol)
+  )
+  const result = {
+    preimages: Array.from(inversion.preimages),
+    errors: Array.from(inversion.errors),
+    monotone: pairs(inversion.monotone),
+    nonInvertible: pairs(inversion.non_invertible),
+    injective: inversion.injective,
   }
-  return (lo + hi) / 2
+  inversion.free()
+  return result
 }
+